@@ -0,0 +1,96 @@
+use anyhow::Result;
+use cgmath::Vector3;
+use computer_graphics_from_scratch_rs::canvas::Canvas;
+use computer_graphics_from_scratch_rs::export::export_png;
+use computer_graphics_from_scratch_rs::primitive::Color;
+use computer_graphics_from_scratch_rs::primitive::Direction;
+use computer_graphics_from_scratch_rs::primitive::Material;
+use computer_graphics_from_scratch_rs::primitive::Plane;
+use computer_graphics_from_scratch_rs::primitive::Position;
+use computer_graphics_from_scratch_rs::primitive::Sphere;
+use computer_graphics_from_scratch_rs::primitive::{Light, LightType};
+use computer_graphics_from_scratch_rs::raytracer::Scene;
+use computer_graphics_from_scratch_rs::render::render;
+
+const EXPORT_PATH: &str = "export/raytracer_15_area_lights.png";
+
+const CANVAS_WIDTH: u32 = 1200;
+const CANVAS_HEIGHT: u32 = 1200;
+
+const VIEWPORT_SIZE: f32 = 1.;
+const PROJECTION_PLANE_Z: f32 = 1.;
+
+const CAMERA_POSITION: Vector3<f32> = Vector3::new(0., 0., 0.);
+
+const BACKGROUND_COLOR: Color = Color::from_srgb8(255, 255, 255);
+
+const SPHERES: &[Sphere] = &[
+  Sphere {
+    center: Vector3::new(0., -1., 3.),
+    radius: 1.,
+    material: Material::new(Color::from_srgb8(255, 0, 0)).with_specular(500.),
+  },
+  Sphere {
+    center: Vector3::new(-2., 0., 4.),
+    radius: 1.,
+    material: Material::new(Color::from_srgb8(0, 255, 0)).with_specular(10.),
+  },
+  Sphere {
+    center: Vector3::new(2., 0., 4.),
+    radius: 1.,
+    material: Material::new(Color::from_srgb8(0, 0, 255)).with_specular(500.),
+  },
+];
+
+const PLANES: &[Plane] = &[Plane {
+  point: Vector3::new(0., -1., 0.),
+  normal: Vector3::new(0., 1., 0.),
+  material: Material::new(Color::from_srgb8(255, 255, 0)).with_specular(1000.),
+}];
+
+/// `n × n` shadow rays per area light
+const SAMPLES: u32 = 8;
+
+const LIGHTS: &[Light] = &[
+  Light::new(LightType::Ambient, 0.2),
+  Light::new(
+    LightType::RectArea {
+      corner: Position::new(0., 2., 1.),
+      edge_u: Direction::new(1.5, 0., 0.),
+      edge_v: Direction::new(0., 0., 1.5),
+      samples: SAMPLES,
+    },
+    0.6,
+  ),
+  Light::new(
+    LightType::SphereArea {
+      center: Position::new(-3., 5., 1.),
+      radius: 1.,
+      samples: SAMPLES,
+    },
+    0.2,
+  ),
+];
+
+fn main() -> Result<()> {
+  let mut canvas = Canvas::new(
+    CANVAS_WIDTH,
+    CANVAS_HEIGHT,
+    VIEWPORT_SIZE,
+    PROJECTION_PLANE_Z,
+  );
+  let scene = Scene::new(BACKGROUND_COLOR)
+    .with_shapes(SPHERES)
+    .with_shapes(PLANES)
+    .with_lights(LIGHTS)
+    .with_shadow();
+
+  render(&mut canvas, |canvas, x, y| {
+    let direction = canvas.canvas_to_viewport(x, y);
+    scene.trace_ray(CAMERA_POSITION, direction, 1., f32::INFINITY, None)
+  });
+
+  export_png(EXPORT_PATH, canvas.data(), CANVAS_WIDTH, CANVAS_HEIGHT)?;
+
+  Ok(())
+}
@@ -0,0 +1,114 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use cgmath::Vector3;
+use computer_graphics_from_scratch_rs::canvas::Canvas;
+use computer_graphics_from_scratch_rs::export::export_png;
+use computer_graphics_from_scratch_rs::primitive::Color;
+use computer_graphics_from_scratch_rs::primitive::Direction;
+use computer_graphics_from_scratch_rs::primitive::Material;
+use computer_graphics_from_scratch_rs::primitive::Position;
+use computer_graphics_from_scratch_rs::primitive::Sphere;
+use computer_graphics_from_scratch_rs::primitive::{Light, LightType};
+use computer_graphics_from_scratch_rs::raytracer::Scene;
+use computer_graphics_from_scratch_rs::render::Renderer;
+
+const EXPORT_PATH: &str = "export/raytracer_09_bvh_benchmark.png";
+
+const CANVAS_WIDTH: u32 = 200;
+const CANVAS_HEIGHT: u32 = 200;
+
+const VIEWPORT_SIZE: f32 = 1.;
+const PROJECTION_PLANE_Z: f32 = 1.;
+
+const CAMERA_POSITION: Vector3<f32> = Vector3::new(0., 0., -60.);
+
+const BACKGROUND_COLOR: Color = Color::from_srgb8(0, 0, 0);
+
+const LIGHTS: &[Light] = &[
+  Light::new(LightType::Ambient, 0.2),
+  Light::new(LightType::Point(Position::new(30., 30., -60.)), 0.6),
+  Light::new(LightType::Directional(Direction::new(1., 4., -4.)), 0.2),
+];
+
+const RECURSION_DEPTH: i32 = 2;
+
+/// 10,000 spheres on a 100×100 grid
+fn spheres() -> Vec<Sphere> {
+  let mut spheres = Vec::new();
+
+  for i in 0..100 {
+    for j in 0..100 {
+      let x = (i as f32 - 49.5) * 0.5;
+      let y = (j as f32 - 49.5) * 0.5;
+      let z = ((i * 7 + j * 13) % 10) as f32 * 0.3;
+
+      let color = Color::from_srgb(i as f32 / 99., j as f32 / 99., 0.5);
+
+      spheres.push(Sphere {
+        center: Vector3::new(x, y, z),
+        radius: 0.2,
+        material: Material::new(color).with_specular(100.).with_reflective(0.3),
+      });
+    }
+  }
+
+  spheres
+}
+
+fn render(scene: &Scene) -> (Canvas, Duration) {
+  let mut canvas = Canvas::new(
+    CANVAS_WIDTH,
+    CANVAS_HEIGHT,
+    VIEWPORT_SIZE,
+    PROJECTION_PLANE_Z,
+  );
+
+  let start = Instant::now();
+
+  // Single threaded, so only the intersection strategy differs
+  Renderer::new().with_threads(1).render(&mut canvas, |canvas, x, y| {
+    let direction = canvas.canvas_to_viewport(x, y);
+
+    scene.trace_ray(
+      CAMERA_POSITION,
+      direction,
+      1.,
+      f32::INFINITY,
+      Some(RECURSION_DEPTH),
+    )
+  });
+
+  (canvas, start.elapsed())
+}
+
+fn main() -> Result<()> {
+  let spheres = spheres();
+
+  let linear = Scene::new(BACKGROUND_COLOR)
+    .with_shapes(&spheres)
+    .with_lights(LIGHTS)
+    .with_shadow()
+    .without_bvh();
+  let accelerated = Scene::new(BACKGROUND_COLOR)
+    .with_shapes(&spheres)
+    .with_lights(LIGHTS)
+    .with_shadow();
+
+  let (linear_canvas, linear_time) = render(&linear);
+  let (canvas, bvh_time) = render(&accelerated);
+
+  println!("{} spheres, {CANVAS_WIDTH}x{CANVAS_HEIGHT}", spheres.len());
+  println!("linear: {linear_time:.2?}");
+  println!("bvh:    {bvh_time:.2?}");
+  println!(
+    "speedup: {:.1}x",
+    linear_time.as_secs_f64() / bvh_time.as_secs_f64()
+  );
+
+  assert_eq!(linear_canvas.data(), canvas.data());
+
+  export_png(EXPORT_PATH, canvas.data(), CANVAS_WIDTH, CANVAS_HEIGHT)?;
+
+  Ok(())
+}
@@ -1,16 +1,17 @@
 use anyhow::Result;
-use cgmath::Basis3;
-use cgmath::Matrix3;
-use cgmath::Rotation3;
 use cgmath::Vector3;
+use computer_graphics_from_scratch_rs::camera::Camera;
 use computer_graphics_from_scratch_rs::canvas::Canvas;
 use computer_graphics_from_scratch_rs::export::export_png;
 use computer_graphics_from_scratch_rs::primitive::Color;
 use computer_graphics_from_scratch_rs::primitive::Direction;
+use computer_graphics_from_scratch_rs::primitive::Material;
+use computer_graphics_from_scratch_rs::primitive::Plane;
 use computer_graphics_from_scratch_rs::primitive::Position;
 use computer_graphics_from_scratch_rs::primitive::Sphere;
 use computer_graphics_from_scratch_rs::primitive::{Light, LightType};
 use computer_graphics_from_scratch_rs::raytracer::Scene;
+use computer_graphics_from_scratch_rs::render::render;
 
 const EXPORT_PATH: &str = "export/raytracer_06_camera_position.png";
 
@@ -21,54 +22,47 @@ const VIEWPORT_SIZE: f32 = 1.;
 const PROJECTION_PLANE_Z: f32 = 1.;
 
 const CAMERA_POSITION: Vector3<f32> = Vector3::new(3., 0., 1.);
-const CAMERA_ROTATION_Y: f32 = -45.;
+// 45° to the left of +z
+const CAMERA_TARGET: Vector3<f32> = Vector3::new(2., 0., 2.);
 
-const BACKGROUND_COLOR: Color = [0., 0., 0., 255.];
+const BACKGROUND_COLOR: Color = Color::from_srgb8(0, 0, 0);
 
 const SPHERES: &[Sphere] = &[
   Sphere {
     center: Vector3::new(0., -1., 3.),
     radius: 1.,
-    color: [255., 0., 0., 255.],
-    specular: Some(500.),
-    reflective: Some(0.2),
+    material: Material::new(Color::from_srgb8(255, 0, 0))
+      .with_specular(500.)
+      .with_reflective(0.2),
   },
   Sphere {
     center: Vector3::new(-2., 0., 4.),
     radius: 1.,
-    color: [0., 255., 0., 255.],
-    specular: Some(10.),
-    reflective: Some(0.4),
+    material: Material::new(Color::from_srgb8(0, 255, 0))
+      .with_specular(10.)
+      .with_reflective(0.4),
   },
   Sphere {
     center: Vector3::new(2., 0., 4.),
     radius: 1.,
-    color: [0., 0., 255., 255.],
-    specular: Some(500.),
-    reflective: Some(0.3),
-  },
-  Sphere {
-    center: Vector3::new(0., -5001., 0.),
-    radius: 5000.,
-    color: [255., 255., 0., 255.],
-    specular: Some(1000.),
-    reflective: Some(0.5),
+    material: Material::new(Color::from_srgb8(0, 0, 255))
+      .with_specular(500.)
+      .with_reflective(0.3),
   },
 ];
 
+const PLANES: &[Plane] = &[Plane {
+  point: Vector3::new(0., -1., 0.),
+  normal: Vector3::new(0., 1., 0.),
+  material: Material::new(Color::from_srgb8(255, 255, 0))
+    .with_specular(1000.)
+    .with_reflective(0.5),
+}];
+
 const LIGHTS: &[Light] = &[
-  Light {
-    ty: LightType::Ambient,
-    intensity: 0.2,
-  },
-  Light {
-    ty: LightType::Point(Position::new(2., 1., 0.)),
-    intensity: 0.6,
-  },
-  Light {
-    ty: LightType::Directional(Direction::new(1., 4., 4.)),
-    intensity: 0.2,
-  },
+  Light::new(LightType::Ambient, 0.2),
+  Light::new(LightType::Point(Position::new(2., 1., 0.)), 0.6),
+  Light::new(LightType::Directional(Direction::new(1., 4., 4.)), 0.2),
 ];
 
 const RECURSION_DEPTH: i32 = 3;
@@ -80,32 +74,20 @@ fn main() -> Result<()> {
     VIEWPORT_SIZE,
     PROJECTION_PLANE_Z,
   );
-  let scene =
-    Scene::new(SPHERES, BACKGROUND_COLOR).with_lights(LIGHTS).with_shadow();
-
-  let cw = CANVAS_WIDTH as i32;
-  let ch = CANVAS_HEIGHT as i32;
-
-  let camera_rotation: Basis3<f32> =
-    Rotation3::from_angle_y(cgmath::Deg(CAMERA_ROTATION_Y));
-  let camera_rotation_matrix: Matrix3<f32> = camera_rotation.into();
+  let scene = Scene::new(BACKGROUND_COLOR)
+    .with_shapes(SPHERES)
+    .with_shapes(PLANES)
+    .with_lights(LIGHTS)
+    .with_shadow();
 
-  for x in -cw / 2..cw / 2 {
-    for y in -ch / 2..ch / 2 {
-      let direction =
-        camera_rotation_matrix * canvas.canvas_to_viewport(x as f32, y as f32);
+  let camera = Camera::look_at(CAMERA_POSITION, CAMERA_TARGET);
+  let rays = camera.rays(&canvas);
 
-      let color = scene.trace_ray(
-        CAMERA_POSITION,
-        direction,
-        1.,
-        f32::INFINITY,
-        Some(RECURSION_DEPTH),
-      );
+  render(&mut canvas, |_, x, y| {
+    let (origin, direction) = rays.ray(x, y);
 
-      canvas.put_pixel(x as f32, y as f32, color);
-    }
-  }
+    scene.trace_ray(origin, direction, 1., f32::INFINITY, Some(RECURSION_DEPTH))
+  });
 
   export_png(EXPORT_PATH, canvas.data(), CANVAS_WIDTH, CANVAS_HEIGHT)?;
 
@@ -0,0 +1,99 @@
+use anyhow::Result;
+use cgmath::Vector3;
+use computer_graphics_from_scratch_rs::canvas::Canvas;
+use computer_graphics_from_scratch_rs::export::export_png;
+use computer_graphics_from_scratch_rs::primitive::Color;
+use computer_graphics_from_scratch_rs::primitive::Direction;
+use computer_graphics_from_scratch_rs::primitive::Material;
+use computer_graphics_from_scratch_rs::primitive::Plane;
+use computer_graphics_from_scratch_rs::primitive::Position;
+use computer_graphics_from_scratch_rs::primitive::Sphere;
+use computer_graphics_from_scratch_rs::primitive::{Light, LightType};
+use computer_graphics_from_scratch_rs::raytracer::Scene;
+use computer_graphics_from_scratch_rs::render::render;
+
+const EXPORT_PATH: &str = "export/raytracer_13_colored_lights.png";
+
+const CANVAS_WIDTH: u32 = 1200;
+const CANVAS_HEIGHT: u32 = 1200;
+
+const VIEWPORT_SIZE: f32 = 1.;
+const PROJECTION_PLANE_Z: f32 = 1.;
+
+const CAMERA_POSITION: Vector3<f32> = Vector3::new(0., 0., 0.);
+
+const BACKGROUND_COLOR: Color = Color::from_srgb8(20, 24, 40);
+
+const SPHERES: &[Sphere] = &[
+  Sphere {
+    center: Vector3::new(0., -1., 3.),
+    radius: 1.,
+    material: Material::new(Color::from_srgb8(230, 230, 230))
+      .with_specular(500.),
+  },
+  Sphere {
+    center: Vector3::new(-2., 0., 4.),
+    radius: 1.,
+    material: Material::new(Color::from_srgb8(230, 230, 230))
+      .with_specular(10.),
+  },
+  Sphere {
+    center: Vector3::new(2., 0., 4.),
+    radius: 1.,
+    material: Material::new(Color::from_srgb8(230, 230, 230))
+      .with_specular(500.)
+      .with_reflective(0.3),
+  },
+];
+
+const PLANES: &[Plane] = &[Plane {
+  point: Vector3::new(0., -1., 0.),
+  normal: Vector3::new(0., 1., 0.),
+  material: Material::new(Color::from_srgb8(200, 200, 200))
+    .with_specular(1000.),
+}];
+
+/// Warm key light against a cool fill and sky
+const LIGHTS: &[Light] = &[
+  Light::colored(LightType::Ambient, Color::new(0.03, 0.04, 0.08)),
+  Light::colored(
+    LightType::Point(Position::new(2., 1., 0.)),
+    Color::new(0.9, 0.55, 0.25),
+  ),
+  Light::colored(
+    LightType::Directional(Direction::new(-1., 4., 4.)),
+    Color::new(0.1, 0.2, 0.45),
+  ),
+];
+
+const RECURSION_DEPTH: i32 = 3;
+
+fn main() -> Result<()> {
+  let mut canvas = Canvas::new(
+    CANVAS_WIDTH,
+    CANVAS_HEIGHT,
+    VIEWPORT_SIZE,
+    PROJECTION_PLANE_Z,
+  );
+  let scene = Scene::new(BACKGROUND_COLOR)
+    .with_shapes(SPHERES)
+    .with_shapes(PLANES)
+    .with_lights(LIGHTS)
+    .with_shadow();
+
+  render(&mut canvas, |canvas, x, y| {
+    let direction = canvas.canvas_to_viewport(x, y);
+
+    scene.trace_ray(
+      CAMERA_POSITION,
+      direction,
+      1.,
+      f32::INFINITY,
+      Some(RECURSION_DEPTH),
+    )
+  });
+
+  export_png(EXPORT_PATH, canvas.data(), CANVAS_WIDTH, CANVAS_HEIGHT)?;
+
+  Ok(())
+}
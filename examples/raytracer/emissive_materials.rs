@@ -0,0 +1,117 @@
+use anyhow::Result;
+use cgmath::Vector3;
+use computer_graphics_from_scratch_rs::canvas::Canvas;
+use computer_graphics_from_scratch_rs::export::export_png;
+use computer_graphics_from_scratch_rs::primitive::Color;
+use computer_graphics_from_scratch_rs::primitive::Disk;
+use computer_graphics_from_scratch_rs::primitive::Material;
+use computer_graphics_from_scratch_rs::primitive::Plane;
+use computer_graphics_from_scratch_rs::primitive::Sphere;
+use computer_graphics_from_scratch_rs::primitive::{Light, LightType};
+use computer_graphics_from_scratch_rs::raytracer::Scene;
+use computer_graphics_from_scratch_rs::render::render;
+use computer_graphics_from_scratch_rs::tonemap::ToneMap;
+
+const EXPORT_PATH: &str = "export/raytracer_21_emissive_materials.png";
+
+const CANVAS_WIDTH: u32 = 1200;
+const CANVAS_HEIGHT: u32 = 1200;
+
+const VIEWPORT_SIZE: f32 = 1.;
+const PROJECTION_PLANE_Z: f32 = 1.;
+
+const CAMERA_POSITION: Vector3<f32> = Vector3::new(0., 0., 0.);
+
+const BACKGROUND_COLOR: Color = Color::from_srgb8(5, 5, 10);
+
+const RECURSION_DEPTH: i32 = 3;
+
+// Shadow rays per emitter: EMISSION_SAMPLES × EMISSION_SAMPLES
+const EMISSION_SAMPLES: u32 = 6;
+
+// The only light besides the glowing shapes
+const LIGHTS: &[Light] = &[Light::new(LightType::Ambient, 0.03)];
+
+const SPHERES: &[Sphere] = &[
+  // Warm lamp
+  Sphere {
+    center: Vector3::new(-1.2, -0.5, 3.5),
+    radius: 0.5,
+    material: Material::new(Color::from_srgb8(255, 200, 120))
+      .with_emission(Color::new(12., 7., 3.)),
+  },
+  // Small cold lamp
+  Sphere {
+    center: Vector3::new(1.1, -0.75, 3.5),
+    radius: 0.25,
+    material: Material::new(Color::from_srgb8(120, 180, 255))
+      .with_emission(Color::new(4., 8., 20.)),
+  },
+  // Lit by both
+  Sphere {
+    center: Vector3::new(0.2, -0.4, 4.5),
+    radius: 0.6,
+    material: Material::new(Color::from_srgb8(230, 230, 230))
+      .with_specular(100.),
+  },
+  Sphere {
+    center: Vector3::new(1.6, 0., 5.),
+    radius: 0.6,
+    material: Material::new(Color::from_srgb8(200, 200, 200)).with_pbr(1., 0.2),
+  },
+];
+
+fn main() -> Result<()> {
+  let mut canvas = Canvas::new(
+    CANVAS_WIDTH,
+    CANVAS_HEIGHT,
+    VIEWPORT_SIZE,
+    PROJECTION_PLANE_Z,
+  )
+  .with_tone_map(ToneMap::AcesFilmic);
+
+  let planes = [
+    Plane {
+      point: Vector3::new(0., -1., 0.),
+      normal: Vector3::new(0., 1., 0.),
+      material: Material::new(Color::from_srgb8(200, 200, 200)),
+    },
+    Plane {
+      point: Vector3::new(0., 0., 6.),
+      normal: Vector3::new(0., 0., -1.),
+      material: Material::new(Color::from_srgb8(200, 200, 200)),
+    },
+  ];
+
+  // Ceiling panel, glowing on both sides
+  let disks = [Disk {
+    center: Vector3::new(0., 1.6, 4.5),
+    normal: Vector3::new(0., -1., 0.),
+    radius: 0.8,
+    material: Material::new(Color::WHITE).with_emission(Color::gray(3.)),
+  }];
+
+  let scene = Scene::new(BACKGROUND_COLOR)
+    .with_shapes(SPHERES)
+    .with_shapes(&planes)
+    .with_shapes(&disks)
+    .with_lights(LIGHTS)
+    .with_shadow()
+    .with_emission_samples(EMISSION_SAMPLES);
+
+  render(&mut canvas, |canvas, x, y| {
+    let direction = canvas.canvas_to_viewport(x, y);
+
+    scene.trace_ray(
+      CAMERA_POSITION,
+      direction,
+      1.,
+      f32::INFINITY,
+      Some(RECURSION_DEPTH),
+    )
+  });
+
+  export_png(EXPORT_PATH, canvas.data(), CANVAS_WIDTH, CANVAS_HEIGHT)?;
+
+  Ok(())
+}
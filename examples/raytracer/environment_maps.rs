@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use cgmath::Vector3;
+use computer_graphics_from_scratch_rs::canvas::Canvas;
+use computer_graphics_from_scratch_rs::environment::CubeMap;
+use computer_graphics_from_scratch_rs::environment::Environment;
+use computer_graphics_from_scratch_rs::environment::EquirectangularMap;
+use computer_graphics_from_scratch_rs::environment::Sky;
+use computer_graphics_from_scratch_rs::export::export_png;
+use computer_graphics_from_scratch_rs::primitive::Color;
+use computer_graphics_from_scratch_rs::primitive::Direction;
+use computer_graphics_from_scratch_rs::primitive::Material;
+use computer_graphics_from_scratch_rs::primitive::Sphere;
+use computer_graphics_from_scratch_rs::primitive::{Light, LightType};
+use computer_graphics_from_scratch_rs::raytracer::Scene;
+use computer_graphics_from_scratch_rs::render::render;
+
+const EXPORT_DIR: &str = "export";
+
+const EQUIRECTANGULAR_PATH: &str = "assets/environments/studio.png";
+const CUBE_MAP_PATHS: [&str; 6] = [
+  "assets/environments/studio_px.png",
+  "assets/environments/studio_nx.png",
+  "assets/environments/studio_py.png",
+  "assets/environments/studio_ny.png",
+  "assets/environments/studio_pz.png",
+  "assets/environments/studio_nz.png",
+];
+
+const CANVAS_WIDTH: u32 = 1200;
+const CANVAS_HEIGHT: u32 = 1200;
+
+const VIEWPORT_SIZE: f32 = 1.;
+const PROJECTION_PLANE_Z: f32 = 1.;
+
+const CAMERA_POSITION: Vector3<f32> = Vector3::new(0., 0., 0.);
+
+// Only seen where no environment is set
+const BACKGROUND_COLOR: Color = Color::BLACK;
+
+const RECURSION_DEPTH: i32 = 3;
+
+const SUN_DIRECTION: Direction = Direction::new(1., 4., -2.);
+
+const SKY: Sky = Sky::new(
+  Color::from_srgb8(70, 120, 200),
+  Color::from_srgb8(210, 225, 240),
+  Color::from_srgb8(90, 80, 70),
+)
+.with_sun(SUN_DIRECTION, Color::new(20., 18., 15.), 0.02);
+
+// No ambient light: the environment lights the spheres instead
+const LIGHTS: &[Light] =
+  &[Light::new(LightType::Directional(SUN_DIRECTION), 0.5)];
+
+const SPHERES: &[Sphere] = &[
+  // Mirror
+  Sphere {
+    center: Vector3::new(0., 0., 4.),
+    radius: 1.,
+    material: Material::new(Color::from_srgb8(240, 240, 240)).with_pbr(1., 0.),
+  },
+  // Rough gold
+  Sphere {
+    center: Vector3::new(-2.2, 0., 5.),
+    radius: 1.,
+    material: Material::new(Color::from_srgb8(255, 195, 85)).with_pbr(1., 0.4),
+  },
+  // Matte white
+  Sphere {
+    center: Vector3::new(2.2, 0., 5.),
+    radius: 1.,
+    material: Material::new(Color::from_srgb8(230, 230, 230)),
+  },
+];
+
+fn main() -> Result<()> {
+  let environments: [(&str, Arc<dyn Environment>); 3] = [
+    ("sky", Arc::new(SKY)),
+    (
+      "equirectangular",
+      Arc::new(EquirectangularMap::load(EQUIRECTANGULAR_PATH)?),
+    ),
+    ("cube_map", Arc::new(CubeMap::load(CUBE_MAP_PATHS)?)),
+  ];
+
+  for (name, environment) in environments {
+    let mut canvas = Canvas::new(
+      CANVAS_WIDTH,
+      CANVAS_HEIGHT,
+      VIEWPORT_SIZE,
+      PROJECTION_PLANE_Z,
+    );
+    let scene = Scene::new(BACKGROUND_COLOR)
+      .with_shapes(SPHERES)
+      .with_lights(LIGHTS)
+      .with_shadow()
+      .with_environment(environment)
+      .with_environment_lighting();
+
+    render(&mut canvas, |canvas, x, y| {
+      let direction = canvas.canvas_to_viewport(x, y);
+
+      scene.trace_ray(
+        CAMERA_POSITION,
+        direction,
+        1.,
+        f32::INFINITY,
+        Some(RECURSION_DEPTH),
+      )
+    });
+
+    let path = format!("{EXPORT_DIR}/raytracer_22_environment_maps_{name}.png");
+    export_png(&path, canvas.data(), CANVAS_WIDTH, CANVAS_HEIGHT)?;
+  }
+
+  Ok(())
+}
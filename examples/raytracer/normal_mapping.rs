@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use cgmath::{InnerSpace, Vector3};
+use computer_graphics_from_scratch_rs::canvas::Canvas;
+use computer_graphics_from_scratch_rs::export::export_png;
+use computer_graphics_from_scratch_rs::noise::Noise;
+use computer_graphics_from_scratch_rs::normal_map::{BumpMap, ImageNormalMap};
+use computer_graphics_from_scratch_rs::primitive::Color;
+use computer_graphics_from_scratch_rs::primitive::Direction;
+use computer_graphics_from_scratch_rs::primitive::Material;
+use computer_graphics_from_scratch_rs::primitive::Plane;
+use computer_graphics_from_scratch_rs::primitive::Position;
+use computer_graphics_from_scratch_rs::primitive::Sphere;
+use computer_graphics_from_scratch_rs::primitive::{Light, LightType};
+use computer_graphics_from_scratch_rs::raytracer::Scene;
+use computer_graphics_from_scratch_rs::render::render;
+use computer_graphics_from_scratch_rs::texture::Image;
+
+const EXPORT_PATH: &str = "export/raytracer_18_normal_mapping.png";
+const NORMAL_MAP_PATH: &str = "assets/textures/tiles_normal.png";
+
+const CANVAS_WIDTH: u32 = 1200;
+const CANVAS_HEIGHT: u32 = 1200;
+
+const VIEWPORT_SIZE: f32 = 1.;
+const PROJECTION_PLANE_Z: f32 = 1.;
+
+const CAMERA_POSITION: Vector3<f32> = Vector3::new(0., 0., 0.);
+
+const BACKGROUND_COLOR: Color = Color::from_srgb8(0, 0, 0);
+
+const LIGHTS: &[Light] = &[
+  Light::new(LightType::Ambient, 0.2),
+  Light::new(LightType::Point(Position::new(2., 1., 0.)), 0.6),
+  Light::new(LightType::Directional(Direction::new(1., 4., 4.)), 0.2),
+];
+
+const RECURSION_DEPTH: i32 = 3;
+
+const SEED: u64 = 7;
+
+fn main() -> Result<()> {
+  let mut canvas = Canvas::new(
+    CANVAS_WIDTH,
+    CANVAS_HEIGHT,
+    VIEWPORT_SIZE,
+    PROJECTION_PLANE_Z,
+  );
+
+  let tiles = Arc::new(Image::load_linear(NORMAL_MAP_PATH)?);
+
+  // Rough plaster: fine noise raising the surface by a few hundredths
+  let noise = Noise::perlin(SEED);
+  let plaster = BumpMap::new(move |p| noise.fbm(p * 8., 4) * 0.02);
+
+  // Rings rippling out from the top of the right sphere
+  let top = Position::new(2., 1., 4.);
+  let ripples =
+    BumpMap::new(move |p| (30. * (p - top).magnitude()).sin() * 0.01);
+
+  let spheres = [
+    Sphere {
+      center: Vector3::new(0., -1., 3.),
+      radius: 1.,
+      material: Material::new(Color::from_srgb8(255, 0, 0))
+        .with_normal_map(Arc::new(
+          ImageNormalMap::new(tiles.clone()).with_scale(8., 4.),
+        ))
+        .with_specular(500.),
+    },
+    Sphere {
+      center: Vector3::new(-2., 0., 4.),
+      radius: 1.,
+      material: Material::new(Color::from_srgb8(0, 255, 0))
+        .with_normal_map(Arc::new(plaster))
+        .with_specular(10.),
+    },
+    Sphere {
+      center: Vector3::new(2., 0., 4.),
+      radius: 1.,
+      material: Material::new(Color::from_srgb8(0, 0, 255))
+        .with_normal_map(Arc::new(ripples))
+        .with_specular(500.)
+        .with_reflective(0.3),
+    },
+  ];
+
+  // Planar UVs are in scene units: one tile every half unit
+  let planes = [Plane {
+    point: Vector3::new(0., -1., 0.),
+    normal: Vector3::new(0., 1., 0.),
+    material: Material::new(Color::from_srgb8(255, 255, 0))
+      .with_normal_map(Arc::new(
+        ImageNormalMap::new(tiles.clone()).with_scale(0.5, 0.5),
+      ))
+      .with_specular(1000.)
+      .with_reflective(0.2),
+  }];
+
+  let scene = Scene::new(BACKGROUND_COLOR)
+    .with_shapes(&spheres)
+    .with_shapes(&planes)
+    .with_lights(LIGHTS)
+    .with_shadow();
+
+  render(&mut canvas, |canvas, x, y| {
+    let direction = canvas.canvas_to_viewport(x, y);
+
+    scene.trace_ray(
+      CAMERA_POSITION,
+      direction,
+      1.,
+      f32::INFINITY,
+      Some(RECURSION_DEPTH),
+    )
+  });
+
+  export_png(EXPORT_PATH, canvas.data(), CANVAS_WIDTH, CANVAS_HEIGHT)?;
+
+  Ok(())
+}
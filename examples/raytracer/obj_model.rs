@@ -0,0 +1,76 @@
+use anyhow::Result;
+use cgmath::Vector3;
+use computer_graphics_from_scratch_rs::canvas::Canvas;
+use computer_graphics_from_scratch_rs::export::export_png;
+use computer_graphics_from_scratch_rs::obj::load_obj;
+use computer_graphics_from_scratch_rs::primitive::Color;
+use computer_graphics_from_scratch_rs::primitive::Direction;
+use computer_graphics_from_scratch_rs::primitive::Material;
+use computer_graphics_from_scratch_rs::primitive::Plane;
+use computer_graphics_from_scratch_rs::primitive::Position;
+use computer_graphics_from_scratch_rs::primitive::{Light, LightType};
+use computer_graphics_from_scratch_rs::raytracer::Scene;
+use computer_graphics_from_scratch_rs::render::render;
+
+const EXPORT_PATH: &str = "export/raytracer_08_obj_model.png";
+const MODEL_PATH: &str = "assets/models/cubes.obj";
+
+const CANVAS_WIDTH: u32 = 1200;
+const CANVAS_HEIGHT: u32 = 1200;
+
+const VIEWPORT_SIZE: f32 = 1.;
+const PROJECTION_PLANE_Z: f32 = 1.;
+
+const CAMERA_POSITION: Vector3<f32> = Vector3::new(0., 0., 0.);
+
+const BACKGROUND_COLOR: Color = Color::from_srgb8(0, 0, 0);
+
+const PLANES: &[Plane] = &[Plane {
+  point: Vector3::new(0., -1., 0.),
+  normal: Vector3::new(0., 1., 0.),
+  material: Material::new(Color::from_srgb8(255, 255, 0))
+    .with_specular(1000.)
+    .with_reflective(0.5),
+}];
+
+const LIGHTS: &[Light] = &[
+  Light::new(LightType::Ambient, 0.2),
+  Light::new(LightType::Point(Position::new(2., 1., 0.)), 0.6),
+  Light::new(LightType::Directional(Direction::new(1., 4., 4.)), 0.2),
+];
+
+const RECURSION_DEPTH: i32 = 3;
+
+fn main() -> Result<()> {
+  let mut canvas = Canvas::new(
+    CANVAS_WIDTH,
+    CANVAS_HEIGHT,
+    VIEWPORT_SIZE,
+    PROJECTION_PLANE_Z,
+  );
+
+  let meshes: Vec<_> =
+    load_obj(MODEL_PATH)?.into_iter().map(|group| group.mesh).collect();
+
+  let scene = Scene::new(BACKGROUND_COLOR)
+    .with_shapes(&meshes)
+    .with_shapes(PLANES)
+    .with_lights(LIGHTS)
+    .with_shadow();
+
+  render(&mut canvas, |canvas, x, y| {
+    let direction = canvas.canvas_to_viewport(x, y);
+
+    scene.trace_ray(
+      CAMERA_POSITION,
+      direction,
+      1.,
+      f32::INFINITY,
+      Some(RECURSION_DEPTH),
+    )
+  });
+
+  export_png(EXPORT_PATH, canvas.data(), CANVAS_WIDTH, CANVAS_HEIGHT)?;
+
+  Ok(())
+}
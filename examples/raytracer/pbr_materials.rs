@@ -0,0 +1,91 @@
+use anyhow::Result;
+use cgmath::Vector3;
+use computer_graphics_from_scratch_rs::canvas::Canvas;
+use computer_graphics_from_scratch_rs::export::export_png;
+use computer_graphics_from_scratch_rs::primitive::Color;
+use computer_graphics_from_scratch_rs::primitive::Direction;
+use computer_graphics_from_scratch_rs::primitive::Material;
+use computer_graphics_from_scratch_rs::primitive::Plane;
+use computer_graphics_from_scratch_rs::primitive::Position;
+use computer_graphics_from_scratch_rs::primitive::Sphere;
+use computer_graphics_from_scratch_rs::primitive::{Light, LightType};
+use computer_graphics_from_scratch_rs::raytracer::Scene;
+use computer_graphics_from_scratch_rs::render::render;
+
+const EXPORT_PATH: &str = "export/raytracer_20_pbr_materials.png";
+
+const CANVAS_WIDTH: u32 = 1200;
+const CANVAS_HEIGHT: u32 = 1200;
+
+const VIEWPORT_SIZE: f32 = 1.;
+const PROJECTION_PLANE_Z: f32 = 1.;
+
+const CAMERA_POSITION: Vector3<f32> = Vector3::new(0., 0., 0.);
+
+const BACKGROUND_COLOR: Color = Color::from_srgb8(30, 30, 40);
+
+const LIGHTS: &[Light] = &[
+  Light::new(LightType::Ambient, 0.2),
+  Light::new(LightType::Point(Position::new(2., 1., 0.)), 0.6),
+  Light::new(LightType::Directional(Direction::new(1., 4., 4.)), 0.2),
+];
+
+const RECURSION_DEPTH: i32 = 3;
+
+const BASE_COLOR: Color = Color::from_srgb8(230, 150, 40);
+
+/// Roughness grows from left to right
+const ROUGHNESS: [f32; 5] = [0.05, 0.25, 0.45, 0.7, 1.];
+
+fn main() -> Result<()> {
+  let mut canvas = Canvas::new(
+    CANVAS_WIDTH,
+    CANVAS_HEIGHT,
+    VIEWPORT_SIZE,
+    PROJECTION_PLANE_Z,
+  );
+
+  // Dielectrics on the top row, metals on the bottom one
+  let spheres: Vec<Sphere> = [(0.45, 0.), (-0.55, 1.)]
+    .into_iter()
+    .flat_map(|(y, metallic)| {
+      ROUGHNESS.iter().enumerate().map(move |(i, &roughness)| Sphere {
+        center: Vector3::new(-2. + i as f32, y, 5.),
+        radius: 0.42,
+        material: Material::new(BASE_COLOR).with_pbr(metallic, roughness),
+      })
+    })
+    .collect();
+
+  // A Phong material carried over to the microfacet model
+  let planes = [Plane {
+    point: Vector3::new(0., -1., 0.),
+    normal: Vector3::new(0., 1., 0.),
+    material: Material::new(Color::from_srgb8(200, 200, 200))
+      .with_specular(1000.)
+      .with_reflective(0.3)
+      .to_pbr(),
+  }];
+
+  let scene = Scene::new(BACKGROUND_COLOR)
+    .with_shapes(&spheres)
+    .with_shapes(&planes)
+    .with_lights(LIGHTS)
+    .with_shadow();
+
+  render(&mut canvas, |canvas, x, y| {
+    let direction = canvas.canvas_to_viewport(x, y);
+
+    scene.trace_ray(
+      CAMERA_POSITION,
+      direction,
+      1.,
+      f32::INFINITY,
+      Some(RECURSION_DEPTH),
+    )
+  });
+
+  export_png(EXPORT_PATH, canvas.data(), CANVAS_WIDTH, CANVAS_HEIGHT)?;
+
+  Ok(())
+}
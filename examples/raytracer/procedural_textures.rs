@@ -0,0 +1,155 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use cgmath::Vector3;
+use computer_graphics_from_scratch_rs::canvas::Canvas;
+use computer_graphics_from_scratch_rs::export::export_png;
+use computer_graphics_from_scratch_rs::noise::Noise;
+use computer_graphics_from_scratch_rs::primitive::Color;
+use computer_graphics_from_scratch_rs::primitive::Direction;
+use computer_graphics_from_scratch_rs::primitive::Material;
+use computer_graphics_from_scratch_rs::primitive::Plane;
+use computer_graphics_from_scratch_rs::primitive::Position;
+use computer_graphics_from_scratch_rs::primitive::Sphere;
+use computer_graphics_from_scratch_rs::primitive::{Light, LightType};
+use computer_graphics_from_scratch_rs::procedural::{Checker, Gradient};
+use computer_graphics_from_scratch_rs::procedural::{Fractal, NoiseTexture};
+use computer_graphics_from_scratch_rs::procedural::{Marble, Stripes, Wood};
+use computer_graphics_from_scratch_rs::raytracer::Scene;
+use computer_graphics_from_scratch_rs::render::render;
+use computer_graphics_from_scratch_rs::texture::Texture;
+
+const EXPORT_PATH: &str = "export/raytracer_17_procedural_textures.png";
+
+const CANVAS_WIDTH: u32 = 1200;
+const CANVAS_HEIGHT: u32 = 1200;
+
+const VIEWPORT_SIZE: f32 = 1.;
+const PROJECTION_PLANE_Z: f32 = 1.;
+
+const CAMERA_POSITION: Vector3<f32> = Vector3::new(0., 0., 0.);
+
+const BACKGROUND_COLOR: Color = Color::from_srgb8(0, 0, 0);
+
+const LIGHTS: &[Light] = &[
+  Light::new(LightType::Ambient, 0.2),
+  Light::new(LightType::Point(Position::new(2., 1., 0.)), 0.6),
+  Light::new(LightType::Directional(Direction::new(1., 4., 4.)), 0.2),
+];
+
+const RECURSION_DEPTH: i32 = 3;
+
+/// Every texture is seeded, so the image is the same on every run
+const SEED: u64 = 7;
+
+fn textured(texture: impl Texture + 'static) -> Material {
+  Material::new(Color::WHITE).with_texture(Arc::new(texture))
+}
+
+fn main() -> Result<()> {
+  let mut canvas = Canvas::new(
+    CANVAS_WIDTH,
+    CANVAS_HEIGHT,
+    VIEWPORT_SIZE,
+    PROJECTION_PLANE_Z,
+  );
+
+  let spheres = [
+    Sphere {
+      center: Vector3::new(0., -1., 3.),
+      radius: 1.,
+      material: textured(Marble::new(SEED)).with_specular(500.),
+    },
+    Sphere {
+      center: Vector3::new(-2., 0., 4.),
+      radius: 1.,
+      material: textured(
+        Wood::new(SEED).with_origin(Position::new(-2., 0., 4.)),
+      )
+      .with_specular(10.),
+    },
+    Sphere {
+      center: Vector3::new(2., 0., 4.),
+      radius: 1.,
+      material: textured(
+        NoiseTexture::new(
+          Noise::simplex(SEED),
+          Color::from_srgb8(20, 40, 120),
+          Color::from_srgb8(240, 240, 255),
+        )
+        .with_scale(0.3)
+        .with_fractal(Fractal::Turbulence),
+      )
+      .with_specular(500.),
+    },
+    Sphere {
+      center: Vector3::new(-0.85, -0.75, 2.),
+      radius: 0.25,
+      material: textured(
+        NoiseTexture::new(
+          Noise::perlin(SEED),
+          Color::from_srgb8(30, 80, 20),
+          Color::from_srgb8(200, 220, 90),
+        )
+        .with_scale(0.1),
+      ),
+    },
+    Sphere {
+      center: Vector3::new(0.85, -0.75, 2.),
+      radius: 0.25,
+      material: textured(Stripes {
+        even: Color::from_srgb8(230, 40, 40),
+        odd: Color::from_srgb8(240, 240, 240),
+        direction: Direction::new(1., 1., 0.),
+        width: 0.08,
+      })
+      .with_specular(100.),
+    },
+  ];
+
+  let planes = [
+    Plane {
+      point: Vector3::new(0., -1., 0.),
+      normal: Vector3::new(0., 1., 0.),
+      material: textured(Checker {
+        even: Color::from_srgb8(40, 40, 40),
+        odd: Color::from_srgb8(220, 220, 220),
+        size: 0.5,
+      })
+      .with_specular(1000.)
+      .with_reflective(0.3),
+    },
+    Plane {
+      point: Vector3::new(0., 0., 10.),
+      normal: Vector3::new(0., 0., -1.),
+      material: textured(Gradient {
+        from: Color::from_srgb8(250, 200, 150),
+        to: Color::from_srgb8(40, 60, 140),
+        start: Position::new(0., -1., 10.),
+        end: Position::new(0., 5., 10.),
+      }),
+    },
+  ];
+
+  let scene = Scene::new(BACKGROUND_COLOR)
+    .with_shapes(&spheres)
+    .with_shapes(&planes)
+    .with_lights(LIGHTS)
+    .with_shadow();
+
+  render(&mut canvas, |canvas, x, y| {
+    let direction = canvas.canvas_to_viewport(x, y);
+
+    scene.trace_ray(
+      CAMERA_POSITION,
+      direction,
+      1.,
+      f32::INFINITY,
+      Some(RECURSION_DEPTH),
+    )
+  });
+
+  export_png(EXPORT_PATH, canvas.data(), CANVAS_WIDTH, CANVAS_HEIGHT)?;
+
+  Ok(())
+}
@@ -0,0 +1,121 @@
+use anyhow::Result;
+use cgmath::{Rad, Vector3};
+use computer_graphics_from_scratch_rs::canvas::Canvas;
+use computer_graphics_from_scratch_rs::export::export_png;
+use computer_graphics_from_scratch_rs::primitive::Color;
+use computer_graphics_from_scratch_rs::primitive::Direction;
+use computer_graphics_from_scratch_rs::primitive::Material;
+use computer_graphics_from_scratch_rs::primitive::Plane;
+use computer_graphics_from_scratch_rs::primitive::Position;
+use computer_graphics_from_scratch_rs::primitive::Sphere;
+use computer_graphics_from_scratch_rs::primitive::{Light, LightType};
+use computer_graphics_from_scratch_rs::raytracer::Scene;
+use computer_graphics_from_scratch_rs::render::render;
+
+const EXPORT_PATH: &str = "export/raytracer_14_spot_lights.png";
+
+const CANVAS_WIDTH: u32 = 1200;
+const CANVAS_HEIGHT: u32 = 1200;
+
+const VIEWPORT_SIZE: f32 = 1.;
+const PROJECTION_PLANE_Z: f32 = 1.;
+
+const CAMERA_POSITION: Vector3<f32> = Vector3::new(0., 0., 0.);
+
+const BACKGROUND_COLOR: Color = Color::from_srgb8(0, 0, 0);
+
+const SPHERES: &[Sphere] = &[
+  Sphere {
+    center: Vector3::new(0., -1., 3.),
+    radius: 1.,
+    material: Material::new(Color::from_srgb8(230, 230, 230))
+      .with_specular(500.),
+  },
+  Sphere {
+    center: Vector3::new(-2., 0., 4.),
+    radius: 1.,
+    material: Material::new(Color::from_srgb8(230, 230, 230))
+      .with_specular(10.),
+  },
+  Sphere {
+    center: Vector3::new(2., 0., 4.),
+    radius: 1.,
+    material: Material::new(Color::from_srgb8(230, 230, 230))
+      .with_specular(500.)
+      .with_reflective(0.3),
+  },
+];
+
+const PLANES: &[Plane] = &[Plane {
+  point: Vector3::new(0., -1., 0.),
+  normal: Vector3::new(0., 1., 0.),
+  material: Material::new(Color::from_srgb8(200, 200, 200))
+    .with_specular(1000.),
+}];
+
+const INNER_ANGLE: Rad<f32> = Rad(0.25);
+const OUTER_ANGLE: Rad<f32> = Rad(0.4);
+
+/// One spot light above each sphere, with a dim ambient so the cones stand out
+const LIGHTS: &[Light] = &[
+  Light::new(LightType::Ambient, 0.05),
+  Light::colored(
+    LightType::Spot {
+      position: Position::new(0., 3., 2.),
+      direction: Direction::new(0., -4., 1.),
+      inner_angle: INNER_ANGLE,
+      outer_angle: OUTER_ANGLE,
+    },
+    Color::new(1., 0.85, 0.6),
+  ),
+  Light::colored(
+    LightType::Spot {
+      position: Position::new(-2., 4., 3.5),
+      direction: Direction::new(0., -4., 1.),
+      inner_angle: INNER_ANGLE,
+      outer_angle: OUTER_ANGLE,
+    },
+    Color::new(0.3, 0.6, 1.),
+  ),
+  Light::colored(
+    LightType::Spot {
+      position: Position::new(2., 4., 3.5),
+      direction: Direction::new(0., -4., 1.),
+      inner_angle: Rad(0.),
+      outer_angle: OUTER_ANGLE,
+    },
+    Color::new(1., 0.4, 0.5),
+  ),
+];
+
+const RECURSION_DEPTH: i32 = 3;
+
+fn main() -> Result<()> {
+  let mut canvas = Canvas::new(
+    CANVAS_WIDTH,
+    CANVAS_HEIGHT,
+    VIEWPORT_SIZE,
+    PROJECTION_PLANE_Z,
+  );
+  let scene = Scene::new(BACKGROUND_COLOR)
+    .with_shapes(SPHERES)
+    .with_shapes(PLANES)
+    .with_lights(LIGHTS)
+    .with_shadow();
+
+  render(&mut canvas, |canvas, x, y| {
+    let direction = canvas.canvas_to_viewport(x, y);
+
+    scene.trace_ray(
+      CAMERA_POSITION,
+      direction,
+      1.,
+      f32::INFINITY,
+      Some(RECURSION_DEPTH),
+    )
+  });
+
+  export_png(EXPORT_PATH, canvas.data(), CANVAS_WIDTH, CANVAS_HEIGHT)?;
+
+  Ok(())
+}
@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use cgmath::Vector3;
+use computer_graphics_from_scratch_rs::canvas::Canvas;
+use computer_graphics_from_scratch_rs::export::export_png;
+use computer_graphics_from_scratch_rs::primitive::Color;
+use computer_graphics_from_scratch_rs::primitive::Direction;
+use computer_graphics_from_scratch_rs::primitive::Material;
+use computer_graphics_from_scratch_rs::primitive::Plane;
+use computer_graphics_from_scratch_rs::primitive::Position;
+use computer_graphics_from_scratch_rs::primitive::Sphere;
+use computer_graphics_from_scratch_rs::primitive::{Light, LightType};
+use computer_graphics_from_scratch_rs::raytracer::Scene;
+use computer_graphics_from_scratch_rs::render::render;
+use computer_graphics_from_scratch_rs::texture::{Image, ImageTexture};
+use computer_graphics_from_scratch_rs::texture::{Interpolation, Wrap};
+
+const EXPORT_PATH: &str = "export/raytracer_16_texture_mapping.png";
+const TEXTURE_PATH: &str = "assets/textures/checker.png";
+
+const CANVAS_WIDTH: u32 = 1200;
+const CANVAS_HEIGHT: u32 = 1200;
+
+const VIEWPORT_SIZE: f32 = 1.;
+const PROJECTION_PLANE_Z: f32 = 1.;
+
+const CAMERA_POSITION: Vector3<f32> = Vector3::new(0., 0., 0.);
+
+const BACKGROUND_COLOR: Color = Color::from_srgb8(0, 0, 0);
+
+const LIGHTS: &[Light] = &[
+  Light::new(LightType::Ambient, 0.2),
+  Light::new(LightType::Point(Position::new(2., 1., 0.)), 0.6),
+  Light::new(LightType::Directional(Direction::new(1., 4., 4.)), 0.2),
+];
+
+const RECURSION_DEPTH: i32 = 3;
+
+fn main() -> Result<()> {
+  let mut canvas = Canvas::new(
+    CANVAS_WIDTH,
+    CANVAS_HEIGHT,
+    VIEWPORT_SIZE,
+    PROJECTION_PLANE_Z,
+  );
+
+  // One low resolution image, so the filtering shows
+  let image = Arc::new(Image::load(TEXTURE_PATH)?);
+  let texture = |interpolation, wrap| {
+    let texture = ImageTexture::new(image.clone())
+      .with_interpolation(interpolation)
+      .with_wrap(wrap);
+    Material::new(Color::WHITE).with_texture(Arc::new(texture))
+  };
+
+  let spheres = [
+    Sphere {
+      center: Vector3::new(0., -1., 3.),
+      radius: 1.,
+      material: texture(Interpolation::Bilinear, Wrap::Repeat)
+        .with_specular(500.),
+    },
+    Sphere {
+      center: Vector3::new(-2., 0., 4.),
+      radius: 1.,
+      material: texture(Interpolation::Nearest, Wrap::Repeat)
+        .with_specular(10.),
+    },
+    Sphere {
+      center: Vector3::new(2., 0., 4.),
+      radius: 1.,
+      material: texture(Interpolation::Bilinear, Wrap::Repeat)
+        .with_specular(500.)
+        .with_reflective(0.3),
+    },
+  ];
+
+  // Planar UVs are in scene units: one copy of the image every 4 units,
+  // mirrored so neighbouring copies line up
+  let floor = ImageTexture::new(image.clone())
+    .with_interpolation(Interpolation::Nearest)
+    .with_wrap(Wrap::MirroredRepeat)
+    .with_scale(0.25, 0.25);
+  let planes = [Plane {
+    point: Vector3::new(0., -1., 0.),
+    normal: Vector3::new(0., 1., 0.),
+    material: Material::new(Color::WHITE)
+      .with_texture(Arc::new(floor))
+      .with_specular(1000.)
+      .with_reflective(0.2),
+  }];
+
+  let scene = Scene::new(BACKGROUND_COLOR)
+    .with_shapes(&spheres)
+    .with_shapes(&planes)
+    .with_lights(LIGHTS)
+    .with_shadow();
+
+  render(&mut canvas, |canvas, x, y| {
+    let direction = canvas.canvas_to_viewport(x, y);
+
+    scene.trace_ray(
+      CAMERA_POSITION,
+      direction,
+      1.,
+      f32::INFINITY,
+      Some(RECURSION_DEPTH),
+    )
+  });
+
+  export_png(EXPORT_PATH, canvas.data(), CANVAS_WIDTH, CANVAS_HEIGHT)?;
+
+  Ok(())
+}
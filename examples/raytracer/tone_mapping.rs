@@ -0,0 +1,114 @@
+use anyhow::Result;
+use cgmath::Vector3;
+use computer_graphics_from_scratch_rs::canvas::Canvas;
+use computer_graphics_from_scratch_rs::export::export_png;
+use computer_graphics_from_scratch_rs::primitive::Color;
+use computer_graphics_from_scratch_rs::primitive::Direction;
+use computer_graphics_from_scratch_rs::primitive::Material;
+use computer_graphics_from_scratch_rs::primitive::Plane;
+use computer_graphics_from_scratch_rs::primitive::Position;
+use computer_graphics_from_scratch_rs::primitive::Sphere;
+use computer_graphics_from_scratch_rs::primitive::{Light, LightType};
+use computer_graphics_from_scratch_rs::raytracer::Scene;
+use computer_graphics_from_scratch_rs::render::render;
+use computer_graphics_from_scratch_rs::tonemap::ToneMap;
+
+const EXPORT_DIR: &str = "export";
+
+/// The same HDR render written once per operator
+const TONE_MAPS: &[(&str, ToneMap)] = &[
+  ("clamp", ToneMap::Clamp),
+  ("reinhard", ToneMap::Reinhard),
+  ("extended_reinhard", ToneMap::ExtendedReinhard { white: 4. }),
+  ("aces_filmic", ToneMap::AcesFilmic),
+];
+
+/// In stops, to bring the bright lights back into range
+const EXPOSURE: f32 = -0.5;
+
+const CANVAS_WIDTH: u32 = 1200;
+const CANVAS_HEIGHT: u32 = 1200;
+
+const VIEWPORT_SIZE: f32 = 1.;
+const PROJECTION_PLANE_Z: f32 = 1.;
+
+const CAMERA_POSITION: Vector3<f32> = Vector3::new(0., 0., 0.);
+
+const BACKGROUND_COLOR: Color = Color::from_srgb8(0, 0, 0);
+
+const SPHERES: &[Sphere] = &[
+  Sphere {
+    center: Vector3::new(0., -1., 3.),
+    radius: 1.,
+    material: Material::new(Color::from_srgb8(255, 0, 0))
+      .with_specular(500.)
+      .with_reflective(0.2),
+  },
+  Sphere {
+    center: Vector3::new(-2., 0., 4.),
+    radius: 1.,
+    material: Material::new(Color::from_srgb8(0, 255, 0))
+      .with_specular(10.)
+      .with_reflective(0.4),
+  },
+  Sphere {
+    center: Vector3::new(2., 0., 4.),
+    radius: 1.,
+    material: Material::new(Color::from_srgb8(0, 0, 255))
+      .with_specular(500.)
+      .with_reflective(0.3),
+  },
+];
+
+const PLANES: &[Plane] = &[Plane {
+  point: Vector3::new(0., -1., 0.),
+  normal: Vector3::new(0., 1., 0.),
+  material: Material::new(Color::from_srgb8(255, 255, 0))
+    .with_specular(1000.)
+    .with_reflective(0.5),
+}];
+
+const LIGHTS: &[Light] = &[
+  Light::new(LightType::Ambient, 0.3),
+  Light::new(LightType::Point(Position::new(2., 1., 0.)), 2.),
+  Light::new(LightType::Directional(Direction::new(1., 4., 4.)), 0.8),
+];
+
+const RECURSION_DEPTH: i32 = 3;
+
+fn main() -> Result<()> {
+  let mut canvas = Canvas::new(
+    CANVAS_WIDTH,
+    CANVAS_HEIGHT,
+    VIEWPORT_SIZE,
+    PROJECTION_PLANE_Z,
+  );
+  let scene = Scene::new(BACKGROUND_COLOR)
+    .with_shapes(SPHERES)
+    .with_shapes(PLANES)
+    .with_lights(LIGHTS)
+    .with_shadow();
+
+  render(&mut canvas, |canvas, x, y| {
+    let direction = canvas.canvas_to_viewport(x, y);
+
+    scene.trace_ray(
+      CAMERA_POSITION,
+      direction,
+      1.,
+      f32::INFINITY,
+      Some(RECURSION_DEPTH),
+    )
+  });
+
+  canvas.set_exposure(EXPOSURE);
+
+  for (name, tone_map) in TONE_MAPS {
+    canvas.set_tone_map(*tone_map);
+
+    let path = format!("{EXPORT_DIR}/raytracer_12_tone_mapping_{name}.png");
+    export_png(&path, canvas.data(), CANVAS_WIDTH, CANVAS_HEIGHT)?;
+  }
+
+  Ok(())
+}
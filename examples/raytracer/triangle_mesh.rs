@@ -0,0 +1,121 @@
+use anyhow::Result;
+use cgmath::Vector3;
+use computer_graphics_from_scratch_rs::canvas::Canvas;
+use computer_graphics_from_scratch_rs::export::export_png;
+use computer_graphics_from_scratch_rs::primitive::Color;
+use computer_graphics_from_scratch_rs::primitive::Direction;
+use computer_graphics_from_scratch_rs::primitive::Material;
+use computer_graphics_from_scratch_rs::primitive::Mesh;
+use computer_graphics_from_scratch_rs::primitive::Plane;
+use computer_graphics_from_scratch_rs::primitive::Position;
+use computer_graphics_from_scratch_rs::primitive::Triangle;
+use computer_graphics_from_scratch_rs::primitive::{Light, LightType};
+use computer_graphics_from_scratch_rs::raytracer::Scene;
+use computer_graphics_from_scratch_rs::render::render;
+
+const EXPORT_PATH: &str = "export/raytracer_07_triangle_mesh.png";
+
+const CANVAS_WIDTH: u32 = 1200;
+const CANVAS_HEIGHT: u32 = 1200;
+
+const VIEWPORT_SIZE: f32 = 1.;
+const PROJECTION_PLANE_Z: f32 = 1.;
+
+const CAMERA_POSITION: Vector3<f32> = Vector3::new(0., 0., 0.);
+
+const BACKGROUND_COLOR: Color = Color::from_srgb8(255, 255, 255);
+
+const TRIANGLES: &[Triangle] = &[Triangle {
+  vertices: [
+    Vector3::new(-3., -1., 6.),
+    Vector3::new(3., -1., 6.),
+    Vector3::new(0., 2.5, 7.),
+  ],
+  material: Material::new(Color::from_srgb8(0, 255, 255)).with_reflective(0.3),
+}];
+
+const PLANES: &[Plane] = &[Plane {
+  point: Vector3::new(0., -1., 0.),
+  normal: Vector3::new(0., 1., 0.),
+  material: Material::new(Color::from_srgb8(255, 255, 0)).with_specular(1000.),
+}];
+
+const LIGHTS: &[Light] = &[
+  Light::new(LightType::Ambient, 0.2),
+  Light::new(LightType::Point(Position::new(2., 1., 0.)), 0.6),
+  Light::new(LightType::Directional(Direction::new(1., 4., 4.)), 0.2),
+];
+
+const RECURSION_DEPTH: i32 = 3;
+
+/// Octahedron centered at `center`, flat shaded unless `smooth`
+fn octahedron(center: Position, radius: f32, smooth: bool) -> Mesh {
+  let positions = [
+    Vector3::new(1., 0., 0.),
+    Vector3::new(-1., 0., 0.),
+    Vector3::new(0., 1., 0.),
+    Vector3::new(0., -1., 0.),
+    Vector3::new(0., 0., 1.),
+    Vector3::new(0., 0., -1.),
+  ]
+  .map(|p| center + p * radius)
+  .to_vec();
+
+  let indices = vec![
+    [0, 2, 4],
+    [4, 2, 1],
+    [1, 2, 5],
+    [5, 2, 0],
+    [4, 3, 0],
+    [1, 3, 4],
+    [5, 3, 1],
+    [0, 3, 5],
+  ];
+
+  let material =
+    Material::new(Color::from_srgb8(255, 0, 255)).with_specular(500.);
+  let mesh = Mesh::new(positions, indices, material);
+
+  if smooth {
+    mesh.with_smooth_normals()
+  } else {
+    mesh
+  }
+}
+
+fn main() -> Result<()> {
+  let mut canvas = Canvas::new(
+    CANVAS_WIDTH,
+    CANVAS_HEIGHT,
+    VIEWPORT_SIZE,
+    PROJECTION_PLANE_Z,
+  );
+
+  let meshes = [
+    octahedron(Position::new(-1.3, 0., 4.), 1., false),
+    octahedron(Position::new(1.3, 0., 4.), 1., true),
+  ];
+
+  let scene = Scene::new(BACKGROUND_COLOR)
+    .with_shapes(&meshes)
+    .with_shapes(TRIANGLES)
+    .with_shapes(PLANES)
+    .with_lights(LIGHTS)
+    .with_shadow();
+
+  render(&mut canvas, |canvas, x, y| {
+    let direction = canvas.canvas_to_viewport(x, y);
+
+    scene.trace_ray(
+      CAMERA_POSITION,
+      direction,
+      1.,
+      f32::INFINITY,
+      Some(RECURSION_DEPTH),
+    )
+  });
+
+  export_png(EXPORT_PATH, canvas.data(), CANVAS_WIDTH, CANVAS_HEIGHT)?;
+
+  Ok(())
+}
@@ -0,0 +1,43 @@
+//! Renders a TOML scene file to a PNG.
+//!
+//! ```bash
+//! cargo run --release --bin render -- scenes/reflections.toml out.png
+//! ```
+
+use std::env;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use computer_graphics_from_scratch_rs::export::export_png;
+use computer_graphics_from_scratch_rs::render::Renderer;
+use computer_graphics_from_scratch_rs::scene_file::SceneFile;
+
+fn main() -> Result<()> {
+  let args: Vec<String> = env::args().collect();
+
+  let (scene_path, output_path) = match args.as_slice() {
+    [_, scene] => {
+      let output = Path::new(scene).with_extension("png");
+      (scene.clone(), output.to_string_lossy().into_owned())
+    }
+    [_, scene, output] => (scene.clone(), output.clone()),
+    _ => bail!("usage: render <scene.toml> [output.png]"),
+  };
+
+  let file = SceneFile::load(&scene_path)?;
+
+  let scene = file.scene();
+  let camera = file.camera();
+  let recursion_depth = Some(file.recursion_depth);
+  let mut canvas = file.canvas();
+  let rays = camera.rays(&canvas);
+
+  Renderer::new().render(&mut canvas, |_, x, y| {
+    let (origin, direction) = rays.ray(x, y);
+    scene.trace_ray(origin, direction, 1., f32::INFINITY, recursion_depth)
+  });
+
+  export_png(&output_path, canvas.data(), canvas.width(), canvas.height())?;
+
+  Ok(())
+}
@@ -0,0 +1,384 @@
+//! Bounding volume hierarchy over axis-aligned bounding boxes, built with the
+//! surface area heuristic.
+
+use cgmath::{ElementWise, Vector3};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+  pub min: Vector3<f32>,
+  pub max: Vector3<f32>,
+}
+
+impl Aabb {
+  pub const EMPTY: Self = Self {
+    min: Vector3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+    max: Vector3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+  };
+
+  pub fn new(min: Vector3<f32>, max: Vector3<f32>) -> Self {
+    Self { min, max }
+  }
+
+  pub fn from_points(points: impl IntoIterator<Item = Vector3<f32>>) -> Self {
+    points.into_iter().fold(Self::EMPTY, Self::grow)
+  }
+
+  pub fn grow(self, point: Vector3<f32>) -> Self {
+    Self {
+      min: vmin(self.min, point),
+      max: vmax(self.max, point),
+    }
+  }
+
+  pub fn union(self, other: Self) -> Self {
+    Self {
+      min: vmin(self.min, other.min),
+      max: vmax(self.max, other.max),
+    }
+  }
+
+  pub fn centroid(&self) -> Vector3<f32> {
+    (self.min + self.max) * 0.5
+  }
+
+  pub fn surface_area(&self) -> f32 {
+    let d = self.max - self.min;
+    if d.x < 0. || d.y < 0. || d.z < 0. {
+      return 0.;
+    }
+    2. * (d.x * d.y + d.y * d.z + d.z * d.x)
+  }
+
+  /// Slab test, returning the distance at which the ray enters the box.
+  ///
+  /// * `O` - origin
+  /// * `inv_D` - component-wise inverse of the direction
+  #[allow(non_snake_case)]
+  pub fn intersect(
+    &self,
+    O: Vector3<f32>,
+    inv_D: Vector3<f32>,
+    min_t: f32,
+    max_t: f32,
+  ) -> Option<f32> {
+    let t1 = (self.min - O).mul_element_wise(inv_D);
+    let t2 = (self.max - O).mul_element_wise(inv_D);
+
+    let mut t_enter = min_t;
+    let mut t_exit = max_t;
+
+    for axis in 0..3 {
+      let (t1, t2) = (t1[axis], t2[axis]);
+
+      // 0 * inf: the ray runs inside the plane of a face, so it can't be
+      // rejected on this axis
+      if t1.is_nan() || t2.is_nan() {
+        continue;
+      }
+
+      t_enter = t_enter.max(t1.min(t2));
+      t_exit = t_exit.min(t1.max(t2));
+    }
+
+    (t_enter <= t_exit).then_some(t_enter)
+  }
+}
+
+fn vmin(a: Vector3<f32>, b: Vector3<f32>) -> Vector3<f32> {
+  Vector3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z))
+}
+
+fn vmax(a: Vector3<f32>, b: Vector3<f32>) -> Vector3<f32> {
+  Vector3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z))
+}
+
+const BIN_COUNT: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+
+/// Relative cost of a ray-box test against a ray-primitive test.
+const TRAVERSAL_COST: f32 = 0.5;
+
+#[derive(Debug, Clone)]
+enum Node {
+  Leaf {
+    bounds: Aabb,
+    start: usize,
+    count: usize,
+  },
+  Interior {
+    bounds: Aabb,
+    left: usize,
+    right: usize,
+  },
+}
+
+impl Node {
+  fn bounds(&self) -> &Aabb {
+    match self {
+      Node::Leaf { bounds, .. } | Node::Interior { bounds, .. } => bounds,
+    }
+  }
+}
+
+/// Hierarchy over primitives identified by their index in the slice of
+/// bounding boxes it was built from.
+#[derive(Debug, Clone)]
+pub struct Bvh {
+  nodes: Vec<Node>,
+  indices: Vec<usize>,
+}
+
+impl Bvh {
+  pub fn build(boxes: &[Aabb]) -> Self {
+    let mut bvh = Self {
+      nodes: Vec::with_capacity(2 * boxes.len()),
+      indices: (0..boxes.len()).collect(),
+    };
+    let centroids: Vec<_> = boxes.iter().map(Aabb::centroid).collect();
+
+    if !boxes.is_empty() {
+      bvh.build_node(boxes, &centroids, 0, boxes.len());
+    }
+
+    bvh
+  }
+
+  /// Builds the node for `indices[start..end]` and returns its index.
+  fn build_node(
+    &mut self,
+    boxes: &[Aabb],
+    centroids: &[Vector3<f32>],
+    start: usize,
+    end: usize,
+  ) -> usize {
+    let items = &mut self.indices[start..end];
+    let bounds = items.iter().fold(Aabb::EMPTY, |b, &i| b.union(boxes[i]));
+    let centroid_bounds =
+      Aabb::from_points(items.iter().map(|&i| centroids[i]));
+
+    let count = end - start;
+    let node = self.nodes.len();
+    let leaf = Node::Leaf {
+      bounds,
+      start,
+      count,
+    };
+
+    let split = if count > 1 {
+      find_split(boxes, centroids, items, &bounds, &centroid_bounds)
+    } else {
+      None
+    };
+
+    let Some((axis, position, cost)) = split else {
+      self.nodes.push(leaf);
+      return node;
+    };
+
+    if count <= MAX_LEAF_SIZE && cost >= count as f32 {
+      self.nodes.push(leaf);
+      return node;
+    }
+
+    // Partition in place around the split plane
+    let mut mid = 0;
+    for i in 0..items.len() {
+      if centroids[items[i]][axis] < position {
+        items.swap(i, mid);
+        mid += 1;
+      }
+    }
+
+    if mid == 0 || mid == count {
+      mid = count / 2;
+    }
+
+    self.nodes.push(leaf);
+    let left = self.build_node(boxes, centroids, start, start + mid);
+    let right = self.build_node(boxes, centroids, start + mid, end);
+    self.nodes[node] = Node::Interior {
+      bounds,
+      left,
+      right,
+    };
+
+    node
+  }
+
+  /// Finds the closest hit, narrowing `max_t` as hits are found.
+  ///
+  /// `intersect` receives a primitive index and the current `max_t`, and
+  /// returns the distance and payload of a hit closer than it.
+  ///
+  /// * `O` - origin
+  /// * `D` - direction
+  #[allow(non_snake_case)]
+  pub fn closest<R>(
+    &self,
+    O: Vector3<f32>,
+    D: Vector3<f32>,
+    min_t: f32,
+    mut max_t: f32,
+    mut intersect: impl FnMut(usize, f32) -> Option<(f32, R)>,
+  ) -> Option<R> {
+    let inv_D = Vector3::new(1. / D.x, 1. / D.y, 1. / D.z);
+    let mut closest = None;
+
+    self.traverse(O, inv_D, min_t, &mut max_t, |index, max_t| {
+      if let Some((t, hit)) = intersect(index, *max_t) {
+        *max_t = t;
+        closest = Some(hit);
+      }
+      false
+    });
+
+    closest
+  }
+
+  /// Returns whether any primitive accepted by `intersect` lies along the
+  /// ray, stopping at the first one.
+  ///
+  /// * `O` - origin
+  /// * `D` - direction
+  #[allow(non_snake_case)]
+  pub fn any(
+    &self,
+    O: Vector3<f32>,
+    D: Vector3<f32>,
+    min_t: f32,
+    mut max_t: f32,
+    mut intersect: impl FnMut(usize) -> bool,
+  ) -> bool {
+    let inv_D = Vector3::new(1. / D.x, 1. / D.y, 1. / D.z);
+
+    self.traverse(O, inv_D, min_t, &mut max_t, |index, _| intersect(index))
+  }
+
+  /// Visits the primitives of every leaf the ray enters, nearest child
+  /// first, until `visit` returns `true`.
+  #[allow(non_snake_case)]
+  fn traverse(
+    &self,
+    O: Vector3<f32>,
+    inv_D: Vector3<f32>,
+    min_t: f32,
+    max_t: &mut f32,
+    mut visit: impl FnMut(usize, &mut f32) -> bool,
+  ) -> bool {
+    let Some(root) = self.nodes.first() else {
+      return false;
+    };
+
+    if root.bounds().intersect(O, inv_D, min_t, *max_t).is_none() {
+      return false;
+    }
+
+    let mut stack = vec![0];
+
+    while let Some(node) = stack.pop() {
+      match self.nodes[node] {
+        Node::Leaf { start, count, .. } => {
+          for &index in &self.indices[start..start + count] {
+            if visit(index, max_t) {
+              return true;
+            }
+          }
+        }
+        Node::Interior { left, right, .. } => {
+          let t_left =
+            self.nodes[left].bounds().intersect(O, inv_D, min_t, *max_t);
+          let t_right =
+            self.nodes[right].bounds().intersect(O, inv_D, min_t, *max_t);
+
+          match (t_left, t_right) {
+            (Some(t_left), Some(t_right)) => {
+              // Push the farther child first so the nearer one is popped
+              if t_left <= t_right {
+                stack.extend([right, left]);
+              } else {
+                stack.extend([left, right]);
+              }
+            }
+            (Some(_), None) => stack.push(left),
+            (None, Some(_)) => stack.push(right),
+            (None, None) => {}
+          }
+        }
+      }
+    }
+
+    false
+  }
+}
+
+/// Bins centroids along each axis and returns the axis, plane position and
+/// SAH cost of the cheapest split, relative to the cost of a leaf primitive.
+#[allow(clippy::needless_range_loop)]
+fn find_split(
+  boxes: &[Aabb],
+  centroids: &[Vector3<f32>],
+  items: &[usize],
+  bounds: &Aabb,
+  centroid_bounds: &Aabb,
+) -> Option<(usize, f32, f32)> {
+  let parent_area = bounds.surface_area();
+  let mut best: Option<(usize, f32, f32)> = None;
+
+  for axis in 0..3 {
+    let lo = centroid_bounds.min[axis];
+    let hi = centroid_bounds.max[axis];
+    let extent = hi - lo;
+
+    if extent <= 0. {
+      continue;
+    }
+
+    let mut bin_bounds = [Aabb::EMPTY; BIN_COUNT];
+    let mut bin_counts = [0usize; BIN_COUNT];
+
+    for &i in items {
+      let bin =
+        ((centroids[i][axis] - lo) / extent * BIN_COUNT as f32) as usize;
+      let bin = bin.min(BIN_COUNT - 1);
+      bin_bounds[bin] = bin_bounds[bin].union(boxes[i]);
+      bin_counts[bin] += 1;
+    }
+
+    // Sweep from the right to get the cost of every right-hand side
+    let mut right_area = [0.; BIN_COUNT];
+    let mut right_count = [0; BIN_COUNT];
+    let mut acc_bounds = Aabb::EMPTY;
+    let mut acc_count = 0;
+
+    for bin in (1..BIN_COUNT).rev() {
+      acc_bounds = acc_bounds.union(bin_bounds[bin]);
+      acc_count += bin_counts[bin];
+      right_area[bin] = acc_bounds.surface_area();
+      right_count[bin] = acc_count;
+    }
+
+    let mut acc_bounds = Aabb::EMPTY;
+    let mut acc_count = 0;
+
+    for bin in 1..BIN_COUNT {
+      acc_bounds = acc_bounds.union(bin_bounds[bin - 1]);
+      acc_count += bin_counts[bin - 1];
+
+      if acc_count == 0 || right_count[bin] == 0 {
+        continue;
+      }
+
+      let cost = TRAVERSAL_COST
+        + (acc_bounds.surface_area() * acc_count as f32
+          + right_area[bin] * right_count[bin] as f32)
+          / parent_area.max(f32::MIN_POSITIVE);
+
+      if best.is_none_or(|(_, _, best_cost)| cost < best_cost) {
+        let position = lo + extent * bin as f32 / BIN_COUNT as f32;
+        best = Some((axis, position, cost));
+      }
+    }
+  }
+
+  best
+}
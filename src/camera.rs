@@ -0,0 +1,105 @@
+use cgmath::{InnerSpace, Rad, Vector3};
+
+use crate::canvas::Canvas;
+use crate::primitive::{Direction, Position};
+
+/// Pinhole camera at `position` looking at `target`.
+///
+/// `fov_y` is the vertical field of view; the horizontal one follows from the
+/// aspect ratio of the canvas being rendered, so pixels stay square.
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+  pub position: Position,
+  pub target: Position,
+  pub up: Direction,
+  pub fov_y: Rad<f32>,
+}
+
+impl Camera {
+  /// Looks at `target` with `+y` up and the field of view of a 1×1
+  /// viewport at distance 1, as `Canvas::canvas_to_viewport` uses.
+  pub fn look_at(position: Position, target: Position) -> Self {
+    Self {
+      position,
+      target,
+      up: Vector3::new(0., 1., 0.),
+      fov_y: Rad(2. * 0.5_f32.atan()),
+    }
+  }
+
+  pub fn with_up(mut self, up: Direction) -> Self {
+    self.up = up;
+    self
+  }
+
+  pub fn with_fov(mut self, fov_y: impl Into<Rad<f32>>) -> Self {
+    self.fov_y = fov_y.into();
+    self
+  }
+
+  /// Orthonormal `(right, up, forward)` basis of the camera. When looking
+  /// along `up`, another axis stands in for it.
+  pub fn basis(&self) -> (Direction, Direction, Direction) {
+    let forward = (self.target - self.position).normalize();
+    let mut right = self.up.cross(forward);
+
+    if right.magnitude2() < 1e-12 {
+      let up = if forward.z.abs() < 0.9 {
+        Vector3::unit_z()
+      } else {
+        Vector3::unit_x()
+      };
+      right = up.cross(forward);
+    }
+
+    let right = right.normalize();
+    let up = forward.cross(right);
+
+    (right, up, forward)
+  }
+
+  /// Ray generator for `canvas`, with the basis and viewport computed once
+  /// for the whole render.
+  pub fn rays(&self, canvas: &Canvas) -> CameraRays {
+    let (right, up, forward) = self.basis();
+
+    let width = canvas.width() as f32;
+    let height = canvas.height() as f32;
+
+    let viewport_height = 2. * (self.fov_y.0 / 2.).tan();
+    let viewport_width = viewport_height * width / height;
+
+    CameraRays {
+      position: self.position,
+      right: right * (viewport_width / width),
+      up: up * (viewport_height / height),
+      forward,
+    }
+  }
+
+  /// Origin and direction of the ray through the canvas point `(x, y)`,
+  /// see [`CameraRays::ray`].
+  pub fn ray(&self, canvas: &Canvas, x: f32, y: f32) -> (Position, Direction) {
+    self.rays(canvas).ray(x, y)
+  }
+}
+
+/// Primary rays of a [`Camera`] through the points of one canvas.
+#[derive(Debug, Clone, Copy)]
+pub struct CameraRays {
+  position: Position,
+  // Scaled to one pixel
+  right: Direction,
+  up: Direction,
+  forward: Direction,
+}
+
+impl CameraRays {
+  /// Origin and direction of the ray through the canvas point `(x, y)`.
+  ///
+  /// The direction reaches the projection plane at `t = 1`, like the ones
+  /// from `Canvas::canvas_to_viewport`.
+  pub fn ray(&self, x: f32, y: f32) -> (Position, Direction) {
+    (self.position, self.forward + self.right * x + self.up * y)
+  }
+}
@@ -1,7 +1,10 @@
 use cgmath::Vector3;
 
 use crate::primitive::Color;
+use crate::tonemap::ToneMap;
 
+/// Keeps the unclamped linear colors next to the sRGB encoded 8-bit RGBA
+/// buffer, so the tone mapping and exposure can change after rendering.
 pub struct Canvas {
   viewport_size: f32,
   projection_plane_z: f32,
@@ -9,7 +12,11 @@ pub struct Canvas {
   canvas_width: f32,
   canvas_height: f32,
 
+  hdr: Vec<Color>,
   data: Vec<u8>,
+
+  tone_map: ToneMap,
+  exposure: f32,
 }
 
 impl Canvas {
@@ -24,38 +31,91 @@ impl Canvas {
       projection_plane_z,
       canvas_width: canvas_width as f32,
       canvas_height: canvas_height as f32,
+      hdr: vec![Color::BLACK; (canvas_width * canvas_height) as usize],
       data: vec![0; (canvas_width * canvas_height * 4) as usize],
+      tone_map: ToneMap::default(),
+      exposure: 0.,
     }
   }
 
+  pub fn with_tone_map(mut self, tone_map: ToneMap) -> Self {
+    self.set_tone_map(tone_map);
+    self
+  }
+
+  /// `exposure` is in stops: every unit doubles the brightness.
+  pub fn with_exposure(mut self, exposure: f32) -> Self {
+    self.set_exposure(exposure);
+    self
+  }
+
+  pub fn set_tone_map(&mut self, tone_map: ToneMap) {
+    self.tone_map = tone_map;
+    self.encode_all();
+  }
+
+  pub fn set_exposure(&mut self, exposure: f32) {
+    self.exposure = exposure;
+    self.encode_all();
+  }
+
+  /// `viewport_size` is the viewport height; its width follows the aspect
+  /// ratio of the canvas.
   pub fn canvas_to_viewport(&self, x: f32, y: f32) -> Vector3<f32> {
-    let x = x * self.viewport_size / self.canvas_width;
+    let x = x * self.viewport_size / self.canvas_height;
     let y = y * self.viewport_size / self.canvas_height;
     let z = self.projection_plane_z;
 
     Vector3::new(x, y, z)
   }
 
+  /// `x` grows to the right and `y` upwards from the center pixel, rounded
+  /// down on canvases with an odd size.
   pub fn put_pixel(&mut self, x: f32, y: f32, color: Color) {
     let w = self.canvas_width;
     let h = self.canvas_height;
 
-    let x = w / 2. + x;
-    let y = h / 2. - y - 1.;
+    let x = (w / 2.).floor() + x.floor();
+    let y = (h / 2.).floor() - y.floor() - 1.;
 
     if x < 0. || y < 0. || x >= w || y >= h {
       return;
     }
 
-    let offset = (4. * (x + y * w)) as usize;
+    let index = x as usize + y as usize * w as usize;
+    self.hdr[index] = color;
+    self.encode(index);
+  }
+
+  fn encode(&mut self, index: usize) {
+    let color = self.hdr[index] * self.exposure.exp2();
+    let [r, g, b] = self.tone_map.apply(color).to_srgb8();
+
+    let offset = 4 * index;
+    self.data[offset..offset + 4].copy_from_slice(&[r, g, b, 255]);
+  }
+
+  fn encode_all(&mut self) {
+    for index in 0..self.hdr.len() {
+      self.encode(index);
+    }
+  }
+
+  pub fn width(&self) -> u32 {
+    self.canvas_width as u32
+  }
 
-    self.data[offset] = color[0] as u8;
-    self.data[offset + 1] = color[1] as u8;
-    self.data[offset + 2] = color[2] as u8;
-    self.data[offset + 3] = color[3] as u8;
+  pub fn height(&self) -> u32 {
+    self.canvas_height as u32
   }
 
+  /// Tone mapped, sRGB encoded RGBA, as written to PNG files.
   pub fn data(&self) -> &[u8] {
     &self.data
   }
+
+  /// Linear colors as shaded, row by row from the top left corner.
+  pub fn hdr_data(&self) -> &[Color] {
+    &self.hdr
+  }
 }
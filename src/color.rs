@@ -0,0 +1,281 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Sub};
+use std::str::FromStr;
+
+use cgmath::Vector3;
+
+/// Linear RGB color. Channels are nominally in `0..=1` but are left
+/// unbounded while shading, so that only the final conversion clamps.
+///
+/// Colors picked by eye (8-bit values, hex strings) are usually sRGB encoded
+/// and should go through the `srgb` conversions, so that light adds up
+/// linearly while shading.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Color {
+  pub r: f32,
+  pub g: f32,
+  pub b: f32,
+}
+
+impl Color {
+  pub const BLACK: Color = Color::new(0., 0., 0.);
+  pub const WHITE: Color = Color::new(1., 1., 1.);
+
+  pub const fn new(r: f32, g: f32, b: f32) -> Self {
+    Self { r, g, b }
+  }
+
+  pub const fn gray(value: f32) -> Self {
+    Self::new(value, value, value)
+  }
+
+  pub const fn from_rgb8(r: u8, g: u8, b: u8) -> Self {
+    Self::new(r as f32 / 255., g as f32 / 255., b as f32 / 255.)
+  }
+
+  pub const fn from_rgb16(r: u16, g: u16, b: u16) -> Self {
+    Self::new(r as f32 / 65535., g as f32 / 65535., b as f32 / 65535.)
+  }
+
+  /// Decodes sRGB encoded channels in `0..=1`.
+  pub const fn from_srgb(r: f32, g: f32, b: f32) -> Self {
+    Self::new(srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b))
+  }
+
+  pub const fn from_srgb8(r: u8, g: u8, b: u8) -> Self {
+    Self::from_srgb(r as f32 / 255., g as f32 / 255., b as f32 / 255.)
+  }
+
+  /// Parses an sRGB `#rrggbb`, the `#` being optional.
+  pub fn from_hex(hex: &str) -> Result<Self, ParseColorError> {
+    let error = || ParseColorError {
+      input: hex.to_string(),
+    };
+
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    // `from_str_radix` would take a `+` sign too
+    if digits.len() != 6 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+      return Err(error());
+    }
+
+    let channel =
+      |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).map_err(|_| error());
+
+    Ok(Self::from_srgb8(channel(0)?, channel(2)?, channel(4)?))
+  }
+
+  pub fn to_rgb8(self) -> [u8; 3] {
+    let c = self.clamp();
+    let quantize = |v: f32| (v * 255.).round() as u8;
+    [quantize(c.r), quantize(c.g), quantize(c.b)]
+  }
+
+  pub fn to_rgb16(self) -> [u16; 3] {
+    let c = self.clamp();
+    let quantize = |v: f32| (v * 65535.).round() as u16;
+    [quantize(c.r), quantize(c.g), quantize(c.b)]
+  }
+
+  /// Clamps and encodes to sRGB.
+  pub fn to_srgb(self) -> Self {
+    let c = self.clamp();
+    Self::new(
+      linear_to_srgb(c.r),
+      linear_to_srgb(c.g),
+      linear_to_srgb(c.b),
+    )
+  }
+
+  /// Decodes a color whose channels hold sRGB values, the counterpart of
+  /// [`Color::to_srgb`].
+  pub fn to_linear(self) -> Self {
+    Self::from_srgb(self.r, self.g, self.b)
+  }
+
+  pub fn to_srgb8(self) -> [u8; 3] {
+    self.to_srgb().to_rgb8()
+  }
+
+  pub fn to_hex(self) -> String {
+    let [r, g, b] = self.to_srgb8();
+    format!("#{r:02x}{g:02x}{b:02x}")
+  }
+
+  /// Clamps every channel to `0..=1`; NaN becomes 0.
+  pub fn clamp(self) -> Self {
+    let clamp = |v: f32| if v > 0. { v.min(1.) } else { 0. };
+    Self::new(clamp(self.r), clamp(self.g), clamp(self.b))
+  }
+
+  /// `self` at `t = 0`, `other` at `t = 1`.
+  pub fn lerp(self, other: Color, t: f32) -> Self {
+    self * (1. - t) + other * t
+  }
+
+  pub fn max_channel(self) -> f32 {
+    self.r.max(self.g).max(self.b)
+  }
+
+  pub fn is_black(self) -> bool {
+    self.r <= 0. && self.g <= 0. && self.b <= 0.
+  }
+}
+
+/// `const` so that materials can stay constants, which rules out `powf`:
+/// `x^2.4` is computed as `x^2 * (x^2)^(1/5)`, the fifth root by Newton's
+/// method.
+const fn srgb_to_linear(c: f32) -> f32 {
+  if c <= 0.04045 {
+    return c / 12.92;
+  }
+
+  let x = (c + 0.055) / 1.055;
+  let square = x * x;
+
+  // Converges from above since the root of a value below 1 is larger
+  let mut root = if square > 1. { square } else { 1. };
+  let mut i = 0;
+  while i < 32 {
+    let pow4 = root * root * root * root;
+    root = (4. * root + square / pow4) / 5.;
+    i += 1;
+  }
+
+  square * root
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+  if c <= 0.0031308 {
+    c * 12.92
+  } else {
+    1.055 * c.powf(1. / 2.4) - 0.055
+  }
+}
+
+impl Add for Color {
+  type Output = Color;
+
+  fn add(self, rhs: Color) -> Color {
+    Color::new(self.r + rhs.r, self.g + rhs.g, self.b + rhs.b)
+  }
+}
+
+impl AddAssign for Color {
+  fn add_assign(&mut self, rhs: Color) {
+    *self = *self + rhs;
+  }
+}
+
+impl Sub for Color {
+  type Output = Color;
+
+  fn sub(self, rhs: Color) -> Color {
+    Color::new(self.r - rhs.r, self.g - rhs.g, self.b - rhs.b)
+  }
+}
+
+/// Component-wise, e.g. to filter light through a surface color.
+impl Mul for Color {
+  type Output = Color;
+
+  fn mul(self, rhs: Color) -> Color {
+    Color::new(self.r * rhs.r, self.g * rhs.g, self.b * rhs.b)
+  }
+}
+
+impl MulAssign for Color {
+  fn mul_assign(&mut self, rhs: Color) {
+    *self = *self * rhs;
+  }
+}
+
+impl Mul<f32> for Color {
+  type Output = Color;
+
+  fn mul(self, rhs: f32) -> Color {
+    Color::new(self.r * rhs, self.g * rhs, self.b * rhs)
+  }
+}
+
+impl Mul<Color> for f32 {
+  type Output = Color;
+
+  fn mul(self, rhs: Color) -> Color {
+    rhs * self
+  }
+}
+
+impl MulAssign<f32> for Color {
+  fn mul_assign(&mut self, rhs: f32) {
+    *self = *self * rhs;
+  }
+}
+
+impl Div<f32> for Color {
+  type Output = Color;
+
+  fn div(self, rhs: f32) -> Color {
+    Color::new(self.r / rhs, self.g / rhs, self.b / rhs)
+  }
+}
+
+impl Sum for Color {
+  fn sum<I: Iterator<Item = Color>>(iter: I) -> Color {
+    iter.fold(Color::BLACK, Add::add)
+  }
+}
+
+impl From<Vector3<f32>> for Color {
+  fn from(v: Vector3<f32>) -> Self {
+    Self::new(v.x, v.y, v.z)
+  }
+}
+
+impl From<Color> for Vector3<f32> {
+  fn from(c: Color) -> Self {
+    Vector3::new(c.r, c.g, c.b)
+  }
+}
+
+impl FromStr for Color {
+  type Err = ParseColorError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::from_hex(s)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseColorError {
+  input: String,
+}
+
+impl fmt::Display for ParseColorError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "invalid hex color {:?}, expected #rrggbb", self.input)
+  }
+}
+
+impl std::error::Error for ParseColorError {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_hex_colors() {
+    assert_eq!(
+      Color::from_hex("#ff8000"),
+      Ok(Color::from_srgb8(255, 128, 0))
+    );
+    assert_eq!(
+      Color::from_hex("FF8000"),
+      Ok(Color::from_srgb8(255, 128, 0))
+    );
+
+    for hex in ["#+f+f+f", "#ff80", "#ff800g", "#ff80é"] {
+      assert!(Color::from_hex(hex).is_err(), "{hex}");
+    }
+  }
+}
@@ -0,0 +1,290 @@
+//! Light coming from infinitely far away, seen by the rays that leave the
+//! scene.
+
+use std::f32::consts::PI;
+use std::fmt::Debug;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Result;
+use cgmath::{InnerSpace, Vector2, Vector3};
+
+use crate::primitive::{Color, Direction, Position};
+use crate::texture::{Image, ImageTexture, Texture, Wrap};
+
+/// Replaces the flat background color of a
+/// [`Scene`](crate::raytracer::Scene).
+pub trait Environment: Debug + Send + Sync {
+  /// Light arriving from `direction`, not necessarily normalized.
+  fn color(&self, direction: Direction) -> Color;
+}
+
+/// The same color in every direction.
+impl Environment for Color {
+  fn color(&self, _direction: Direction) -> Color {
+    *self
+  }
+}
+
+/// Texture coordinates of `direction` in an equirectangular image seen from
+/// inside: `u` grows turning right, from `-z` behind through `+z` straight
+/// ahead in the middle of the image, and `v` from straight down to straight
+/// up.
+pub fn equirectangular_uv(direction: Direction) -> Vector2<f32> {
+  let d = direction.normalize();
+  let u = 0.5 + d.x.atan2(d.z) / (2. * PI);
+  let v = 0.5 + d.y.clamp(-1., 1.).asin() / PI;
+
+  Vector2::new(u, v)
+}
+
+/// Panorama covering every direction in a single image, twice as wide as
+/// it is high, see [`equirectangular_uv`].
+#[derive(Debug, Clone)]
+pub struct EquirectangularMap {
+  texture: ImageTexture,
+  intensity: f32,
+}
+
+impl EquirectangularMap {
+  pub fn new(image: impl Into<Arc<Image>>) -> Self {
+    Self {
+      texture: ImageTexture::new(image),
+      intensity: 1.,
+    }
+  }
+
+  /// Loads an sRGB PNG, see [`Image::load`].
+  pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+    Ok(Self::new(Image::load(path)?))
+  }
+
+  /// Scales the colors of the image, which cannot be brighter than white
+  /// on their own.
+  pub fn with_intensity(mut self, intensity: f32) -> Self {
+    self.intensity = intensity;
+    self
+  }
+}
+
+impl Environment for EquirectangularMap {
+  fn color(&self, direction: Direction) -> Color {
+    let uv = equirectangular_uv(direction);
+    self.texture.color(uv, Position::new(0., 0., 0.)) * self.intensity
+  }
+}
+
+/// Six square images on the faces of a cube around the scene, in the order
+/// `+x`, `-x`, `+y`, `-y`, `+z`, `-z`, laid out as for OpenGL cube maps:
+/// the side faces are seen upright, the top one with `+z` at its bottom
+/// and the bottom one with `+z` at its top.
+#[derive(Debug, Clone)]
+pub struct CubeMap {
+  faces: [ImageTexture; 6],
+  intensity: f32,
+}
+
+impl CubeMap {
+  pub fn new(faces: [Image; 6]) -> Self {
+    Self {
+      faces: faces
+        .map(|face| ImageTexture::new(face).with_wrap(Wrap::ClampToEdge)),
+      intensity: 1.,
+    }
+  }
+
+  /// Loads six sRGB PNGs, in the order of [`CubeMap::new`].
+  pub fn load<P: AsRef<Path>>(paths: [P; 6]) -> Result<Self> {
+    let [px, nx, py, ny, pz, nz] = paths;
+
+    Ok(Self::new([
+      Image::load(px)?,
+      Image::load(nx)?,
+      Image::load(py)?,
+      Image::load(ny)?,
+      Image::load(pz)?,
+      Image::load(nz)?,
+    ]))
+  }
+
+  /// See [`EquirectangularMap::with_intensity`].
+  pub fn with_intensity(mut self, intensity: f32) -> Self {
+    self.intensity = intensity;
+    self
+  }
+}
+
+impl Environment for CubeMap {
+  fn color(&self, direction: Direction) -> Color {
+    let Vector3 { x, y, z } = direction;
+    let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+
+    // Major axis, then the coordinates across the face, from its top left
+    // corner
+    let (face, s, t, major) = if ax >= ay && ax >= az {
+      if x > 0. {
+        (0, -z, -y, ax)
+      } else {
+        (1, z, -y, ax)
+      }
+    } else if ay >= az {
+      if y > 0. {
+        (2, x, z, ay)
+      } else {
+        (3, x, -z, ay)
+      }
+    } else if z > 0. {
+      (4, x, -y, az)
+    } else {
+      (5, -x, -y, az)
+    };
+
+    if major <= 0. {
+      return Color::BLACK;
+    }
+
+    let uv = Vector2::new(0.5 + 0.5 * s / major, 0.5 - 0.5 * t / major);
+    self.faces[face].color(uv, Position::new(0., 0., 0.)) * self.intensity
+  }
+}
+
+/// Analytic sky: a gradient from `horizon` up to `zenith`, `ground` below
+/// the horizon, and optionally a sun.
+#[derive(Debug, Clone, Copy)]
+pub struct Sky {
+  pub zenith: Color,
+  pub horizon: Color,
+  pub ground: Color,
+  pub sun: Option<Sun>,
+}
+
+/// Disk of the sky of angular radius `size`, around `direction`.
+#[derive(Debug, Clone, Copy)]
+pub struct Sun {
+  pub direction: Direction,
+  pub color: Color,
+  pub size: f32,
+}
+
+impl Sky {
+  pub const fn new(zenith: Color, horizon: Color, ground: Color) -> Self {
+    Self {
+      zenith,
+      horizon,
+      ground,
+      sun: None,
+    }
+  }
+
+  pub const fn with_sun(
+    mut self,
+    direction: Direction,
+    color: Color,
+    size: f32,
+  ) -> Self {
+    self.sun = Some(Sun {
+      direction,
+      color,
+      size,
+    });
+    self
+  }
+}
+
+impl Environment for Sky {
+  fn color(&self, direction: Direction) -> Color {
+    let d = direction.normalize();
+
+    // Quickly darkening into the ground, slowly brightening into the sky
+    let mut color = if d.y < 0. {
+      self.horizon.lerp(self.ground, (-d.y * 10.).min(1.))
+    } else {
+      self.horizon.lerp(self.zenith, d.y.sqrt())
+    };
+
+    if let Some(sun) = self.sun {
+      if d.dot(sun.direction.normalize()) >= sun.size.cos() {
+        color = sun.color;
+      }
+    }
+
+    color
+  }
+}
+
+/// Diffuse light an environment sheds on every normal, as with ambient
+/// lights, kept as spherical harmonics up to the second order (Ramamoorthi
+/// and Hanrahan, 2001).
+#[derive(Debug, Clone)]
+pub(crate) struct Irradiance {
+  coefficients: [Color; 9],
+}
+
+impl Irradiance {
+  /// Integrates `environment` over a grid of equal solid angle cells.
+  pub(crate) fn new(environment: &dyn Environment) -> Self {
+    const BANDS: u32 = 64;
+    const SLICES: u32 = 2 * BANDS;
+
+    let mut coefficients = [Color::BLACK; 9];
+    let solid_angle = 4. * PI / (BANDS * SLICES) as f32;
+
+    for i in 0..BANDS {
+      let y = 1. - 2. * (i as f32 + 0.5) / BANDS as f32;
+      let r = (1. - y * y).max(0.).sqrt();
+
+      for j in 0..SLICES {
+        let phi = 2. * PI * (j as f32 + 0.5) / SLICES as f32;
+        let direction = Vector3::new(r * phi.cos(), y, r * phi.sin());
+        let color = environment.color(direction) * solid_angle;
+
+        for (coefficient, basis) in
+          coefficients.iter_mut().zip(sh_basis(direction))
+        {
+          *coefficient += color * basis;
+        }
+      }
+    }
+
+    // Convolution with the clamped cosine, over π so that a uniform
+    // environment gives back its color
+    let bands = [1., 2. / 3., 2. / 3., 2. / 3., 0.25, 0.25, 0.25, 0.25, 0.25];
+    for (coefficient, band) in coefficients.iter_mut().zip(bands) {
+      *coefficient *= band;
+    }
+
+    Self { coefficients }
+  }
+
+  /// Like the intensity of an ambient light, for a surface facing `normal`.
+  pub(crate) fn at(&self, normal: Direction) -> Color {
+    let n = normal.normalize();
+    let color: Color = self
+      .coefficients
+      .iter()
+      .zip(sh_basis(n))
+      .map(|(coefficient, basis)| *coefficient * basis)
+      .sum();
+
+    // Ringing may dip below 0 opposite strong lights
+    Color::new(color.r.max(0.), color.g.max(0.), color.b.max(0.))
+  }
+}
+
+/// Real spherical harmonics of orders 0 to 2 in direction `d`, a unit
+/// vector.
+fn sh_basis(d: Direction) -> [f32; 9] {
+  let Vector3 { x, y, z } = d;
+
+  [
+    0.282_095,
+    0.488_603 * y,
+    0.488_603 * z,
+    0.488_603 * x,
+    1.092_548 * x * y,
+    1.092_548 * y * z,
+    0.315_392 * (3. * z * z - 1.),
+    1.092_548 * x * z,
+    0.546_274 * (x * x - y * y),
+  ]
+}
@@ -2,6 +2,9 @@ use anyhow::Result;
 use std::fs::File;
 use std::io::BufWriter;
 
+/// `data` is sRGB encoded RGBA, as
+/// [`Canvas::data`](crate::canvas::Canvas::data) returns it; the file is
+/// tagged accordingly.
 pub fn export_png(
   path: &str,
   data: &[u8],
@@ -9,13 +12,14 @@ pub fn export_png(
   height: u32,
 ) -> Result<()> {
   let file = File::create(path)?;
-  let ref mut w = BufWriter::new(file);
+  let w = BufWriter::new(file);
 
   let mut png_encoder = png::Encoder::new(w, width, height);
   png_encoder.set_color(png::ColorType::Rgba);
+  png_encoder.set_srgb(png::SrgbRenderingIntent::Perceptual);
 
   let mut writer = png_encoder.write_header()?;
-  writer.write_image_data(&data)?;
+  writer.write_image_data(data)?;
 
   Ok(())
 }
@@ -1,4 +1,21 @@
+pub mod bvh;
+pub mod camera;
 pub mod canvas;
+pub mod color;
+pub mod environment;
 pub mod export;
+pub mod microfacet;
+pub mod noise;
+pub mod normal_map;
+pub mod obj;
+pub mod path_tracer;
 pub mod primitive;
+pub mod procedural;
+pub mod random;
 pub mod raytracer;
+pub mod render;
+pub mod sampling;
+pub mod scene_file;
+pub mod shape;
+pub mod texture;
+pub mod tonemap;
@@ -0,0 +1,131 @@
+//! Cook–Torrance microfacet reflection: GGX distribution, Smith geometry and
+//! Schlick's Fresnel approximation, in the metallic-roughness
+//! parametrization.
+//!
+//! BRDFs here are multiplied by π, so that a white Lambertian surface lit by
+//! a light of intensity `I` under an angle `θ` sends back `I cos θ`, as with
+//! the Phong model of [`Scene::trace_ray`](crate::raytracer::Scene::trace_ray).
+
+use std::f32::consts::PI;
+
+use cgmath::{InnerSpace, Vector3};
+
+use crate::primitive::{Color, Direction, Pbr};
+
+/// Below this the distribution becomes too sharp for `f32`.
+const MIN_ROUGHNESS: f32 = 0.03;
+
+/// Reflectance at normal incidence: 4% for dielectrics, the base color for
+/// metals.
+pub fn base_reflectance(base: Color, metallic: f32) -> Color {
+  Color::gray(0.04).lerp(base, metallic)
+}
+
+/// Width of the GGX lobe, `roughness²` as perceptually linear roughness.
+pub fn alpha(roughness: f32) -> f32 {
+  let roughness = roughness.clamp(MIN_ROUGHNESS, 1.);
+  roughness * roughness
+}
+
+/// GGX (Trowbridge–Reitz) normal distribution.
+pub fn distribution(n_dot_h: f32, alpha: f32) -> f32 {
+  let a2 = alpha * alpha;
+  let d = n_dot_h * n_dot_h * (a2 - 1.) + 1.;
+  a2 / (PI * d * d)
+}
+
+/// Smith's shadowing-masking term, with the Schlick-GGX approximation for
+/// each direction.
+pub fn geometry(n_dot_v: f32, n_dot_l: f32, alpha: f32) -> f32 {
+  let k = alpha / 2.;
+  let g1 = |n_dot_x: f32| n_dot_x / (n_dot_x * (1. - k) + k);
+  g1(n_dot_v) * g1(n_dot_l)
+}
+
+/// Schlick's approximation of the Fresnel reflectance.
+pub fn fresnel(cos: f32, f0: Color) -> Color {
+  let t = (1. - cos.clamp(0., 1.)).powi(5);
+  f0 + (Color::WHITE - f0) * t
+}
+
+/// Fresnel reflectance averaged over the lobe of a rough surface, for light
+/// coming from everywhere rather than one direction.
+pub fn fresnel_rough(cos: f32, f0: Color, roughness: f32) -> Color {
+  let t = (1. - cos.clamp(0., 1.)).powi(5);
+  let max = Color::gray(1. - roughness.clamp(0., 1.));
+  let max = Color::new(max.r.max(f0.r), max.g.max(f0.g), max.b.max(f0.b));
+  f0 + (max - f0) * t
+}
+
+/// π times the BRDF, from light coming from `L` to the viewer in `V`.
+///
+/// * `N` - normal
+/// * `V` - view
+/// * `L` - light
+#[allow(non_snake_case)]
+pub fn brdf(
+  base: Color,
+  pbr: Pbr,
+  N: Direction,
+  V: Direction,
+  L: Direction,
+) -> Color {
+  let (V, L) = (V.normalize(), L.normalize());
+  let n_dot_l = N.dot(L);
+  let n_dot_v = N.dot(V);
+
+  if n_dot_l <= 0. || n_dot_v <= 0. {
+    return Color::BLACK;
+  }
+
+  let H = (V + L).normalize();
+  let alpha = alpha(pbr.roughness);
+  let f0 = base_reflectance(base, pbr.metallic);
+
+  let F = fresnel(V.dot(H), f0);
+  let specular = F
+    * (PI
+      * distribution(N.dot(H).max(0.), alpha)
+      * geometry(n_dot_v, n_dot_l, alpha)
+      / (4. * n_dot_v * n_dot_l));
+
+  // Light not reflected by the surface enters it, and metals absorb it
+  let diffuse = (Color::WHITE - F) * base * (1. - pbr.metallic);
+
+  diffuse + specular
+}
+
+/// Half vector around `z` distributed like the GGX normals, for `(u, v)` in
+/// `[0, 1)²`.
+pub fn sample_half_vector(u: f32, v: f32, alpha: f32) -> Vector3<f32> {
+  let phi = 2. * PI * v;
+  let cos2_theta = (1. - u) / (1. + (alpha * alpha - 1.) * u);
+  let cos_theta = cos2_theta.sqrt();
+  let sin_theta = (1. - cos2_theta).max(0.).sqrt();
+
+  Vector3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+/// Density of the light directions reflected off half vectors drawn by
+/// [`sample_half_vector`].
+///
+/// * `N` - normal
+/// * `V` - view
+/// * `L` - light
+#[allow(non_snake_case)]
+pub fn half_vector_pdf(
+  N: Direction,
+  V: Direction,
+  L: Direction,
+  alpha: f32,
+) -> f32 {
+  let H = (V.normalize() + L.normalize()).normalize();
+  let v_dot_h = V.normalize().dot(H);
+
+  if v_dot_h <= 0. {
+    return 0.;
+  }
+
+  let n_dot_h = N.dot(H).max(0.);
+  distribution(n_dot_h, alpha) * n_dot_h / (4. * v_dot_h)
+}
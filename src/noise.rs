@@ -0,0 +1,209 @@
+//! Seedable gradient noise in 3D, and fractal sums of it.
+
+use cgmath::Vector3;
+
+use crate::primitive::Position;
+use crate::random::Rng;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoiseKind {
+  /// Ken Perlin's improved noise, on a cubic lattice.
+  #[default]
+  Perlin,
+  /// Simplex noise, cheaper and with fewer axis aligned artifacts.
+  Simplex,
+}
+
+/// Smooth pseudo-random function of space, in about `[-1, 1]` and varying
+/// over distances of about 1. The same seed always gives the same noise.
+#[derive(Debug, Clone)]
+pub struct Noise {
+  kind: NoiseKind,
+  // Shuffled 0..256, twice, so lookups can add offsets without wrapping
+  permutation: Box<[u8; 512]>,
+}
+
+impl Noise {
+  pub fn new(kind: NoiseKind, seed: u64) -> Self {
+    let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+
+    let mut rng = Rng::new(seed);
+    for i in (1..table.len()).rev() {
+      let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+      table.swap(i, j);
+    }
+
+    Self {
+      kind,
+      permutation: Box::new(std::array::from_fn(|i| table[i % 256])),
+    }
+  }
+
+  pub fn perlin(seed: u64) -> Self {
+    Self::new(NoiseKind::Perlin, seed)
+  }
+
+  pub fn simplex(seed: u64) -> Self {
+    Self::new(NoiseKind::Simplex, seed)
+  }
+
+  pub fn kind(&self) -> NoiseKind {
+    self.kind
+  }
+
+  pub fn sample(&self, p: Position) -> f32 {
+    match self.kind {
+      NoiseKind::Perlin => self.perlin_noise(p),
+      NoiseKind::Simplex => self.simplex_noise(p),
+    }
+  }
+
+  /// Fractal Brownian motion: `octaves` layers of noise, each at twice the
+  /// frequency and half the amplitude of the previous one. Stays in about
+  /// `[-1, 1]`.
+  pub fn fbm(&self, p: Position, octaves: u32) -> f32 {
+    self.fractal(p, octaves, |n| n)
+  }
+
+  /// Like [`Noise::fbm`] but summing absolute values, which creases the
+  /// noise into billows. In about `[0, 1]`.
+  pub fn turbulence(&self, p: Position, octaves: u32) -> f32 {
+    self.fractal(p, octaves, f32::abs)
+  }
+
+  fn fractal(&self, p: Position, octaves: u32, f: impl Fn(f32) -> f32) -> f32 {
+    let mut sum = 0.;
+    let mut total_amplitude = 0.;
+    let mut amplitude = 1.;
+    let mut frequency = 1.;
+
+    for _ in 0..octaves.max(1) {
+      sum += amplitude * f(self.sample(p * frequency));
+      total_amplitude += amplitude;
+      amplitude *= 0.5;
+      frequency *= 2.;
+    }
+
+    sum / total_amplitude
+  }
+
+  fn hash(&self, x: i32, y: i32, z: i32) -> u8 {
+    let p = &self.permutation;
+    let x = p[(x & 255) as usize] as usize;
+    let y = p[x + (y & 255) as usize] as usize;
+    p[y + (z & 255) as usize]
+  }
+
+  fn perlin_noise(&self, p: Position) -> f32 {
+    let cell = p.map(f32::floor);
+    let Vector3 { x, y, z } = p - cell;
+    let [xi, yi, zi] = [cell.x, cell.y, cell.z].map(|c| c as i32);
+
+    let fade = |t: f32| t * t * t * (t * (t * 6. - 15.) + 10.);
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+
+    let corner = |dx: i32, dy: i32, dz: i32| {
+      let hash = self.hash(xi + dx, yi + dy, zi + dz);
+      perlin_gradient(hash, x - dx as f32, y - dy as f32, z - dz as f32)
+    };
+
+    let lerp = |t: f32, a: f32, b: f32| a + t * (b - a);
+
+    lerp(
+      w,
+      lerp(
+        v,
+        lerp(u, corner(0, 0, 0), corner(1, 0, 0)),
+        lerp(u, corner(0, 1, 0), corner(1, 1, 0)),
+      ),
+      lerp(
+        v,
+        lerp(u, corner(0, 0, 1), corner(1, 0, 1)),
+        lerp(u, corner(0, 1, 1), corner(1, 1, 1)),
+      ),
+    )
+  }
+
+  /// Stefan Gustavson's formulation.
+  fn simplex_noise(&self, p: Position) -> f32 {
+    const F3: f32 = 1. / 3.;
+    const G3: f32 = 1. / 6.;
+
+    // Skew to find the cell, a cube split into six tetrahedra
+    let s = (p.x + p.y + p.z) * F3;
+    let cell = (p + Vector3::new(s, s, s)).map(f32::floor);
+    let t = (cell.x + cell.y + cell.z) * G3;
+    let d0 = p - (cell - Vector3::new(t, t, t));
+
+    // Which tetrahedron, by the order of the coordinates
+    let (o1, o2) = if d0.x >= d0.y {
+      if d0.y >= d0.z {
+        ([1, 0, 0], [1, 1, 0])
+      } else if d0.x >= d0.z {
+        ([1, 0, 0], [1, 0, 1])
+      } else {
+        ([0, 0, 1], [1, 0, 1])
+      }
+    } else if d0.y < d0.z {
+      ([0, 0, 1], [0, 1, 1])
+    } else if d0.x < d0.z {
+      ([0, 1, 0], [0, 1, 1])
+    } else {
+      ([0, 1, 0], [1, 1, 0])
+    };
+
+    let [xi, yi, zi] = [cell.x, cell.y, cell.z].map(|c| c as i32);
+
+    [[0, 0, 0], o1, o2, [1, 1, 1]]
+      .into_iter()
+      .enumerate()
+      .map(|(i, [ox, oy, oz])| {
+        let offset = Vector3::new(ox as f32, oy as f32, oz as f32);
+        let d = d0 - offset + Vector3::new(G3, G3, G3) * i as f32;
+
+        let falloff = 0.6 - d.x * d.x - d.y * d.y - d.z * d.z;
+        if falloff <= 0. {
+          return 0.;
+        }
+
+        let hash = self.hash(xi + ox, yi + oy, zi + oz);
+        let falloff = falloff * falloff;
+        falloff * falloff * simplex_gradient(hash, d)
+      })
+      .sum::<f32>()
+      * 32.
+  }
+}
+
+/// Dot product with one of 12 edge directions of a cube, picked by `hash`.
+fn perlin_gradient(hash: u8, x: f32, y: f32, z: f32) -> f32 {
+  let h = hash & 15;
+  let u = if h < 8 { x } else { y };
+  let v = match h {
+    0..=3 => y,
+    12 | 14 => x,
+    _ => z,
+  };
+
+  (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+fn simplex_gradient(hash: u8, d: Vector3<f32>) -> f32 {
+  const GRADIENTS: [[f32; 3]; 12] = [
+    [1., 1., 0.],
+    [-1., 1., 0.],
+    [1., -1., 0.],
+    [-1., -1., 0.],
+    [1., 0., 1.],
+    [-1., 0., 1.],
+    [1., 0., -1.],
+    [-1., 0., -1.],
+    [0., 1., 1.],
+    [0., -1., 1.],
+    [0., 1., -1.],
+    [0., -1., -1.],
+  ];
+
+  let [gx, gy, gz] = GRADIENTS[hash as usize % 12];
+  gx * d.x + gy * d.y + gz * d.z
+}
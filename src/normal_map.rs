@@ -0,0 +1,205 @@
+//! Small surface detail faked by changing the normal used for shading,
+//! without moving the geometry.
+
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Result;
+use cgmath::{InnerSpace, Vector3};
+
+use crate::primitive::{Direction, Position};
+use crate::shape::Hit;
+use crate::texture::{Image, ImageTexture, Texture, Wrap};
+
+/// Perturbs the normal of a [`Material`](crate::primitive::Material) for
+/// diffuse, specular and reflection.
+pub trait NormalMap: fmt::Debug + Send + Sync {
+  /// Returns the unit normal to shade `hit` with, on the same side of the
+  /// surface as `hit.normal`.
+  fn normal(&self, hit: &Hit) -> Direction;
+}
+
+/// Tangent-space normal map: red, green and blue hold the normal along the
+/// tangent, the bitangent and the geometric normal, remapped from `[-1, 1]`
+/// to `[0, 1]`. Green points towards growing `v`, as in OpenGL.
+#[derive(Debug, Clone)]
+pub struct ImageNormalMap {
+  texture: ImageTexture,
+  strength: f32,
+}
+
+impl ImageNormalMap {
+  pub fn new(image: impl Into<Arc<Image>>) -> Self {
+    Self {
+      texture: ImageTexture::new(image),
+      strength: 1.,
+    }
+  }
+
+  /// Loads a PNG, whose values are taken as they are rather than as sRGB.
+  pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+    Ok(Self::new(Image::load_linear(path)?))
+  }
+
+  pub fn with_wrap(mut self, wrap: Wrap) -> Self {
+    self.texture = self.texture.with_wrap(wrap);
+    self
+  }
+
+  pub fn with_scale(mut self, u: f32, v: f32) -> Self {
+    self.texture = self.texture.with_scale(u, v);
+    self
+  }
+
+  /// Scales the tilt of the normals: 0 flattens the map, above 1 deepens it.
+  pub fn with_strength(mut self, strength: f32) -> Self {
+    self.strength = strength;
+    self
+  }
+}
+
+impl NormalMap for ImageNormalMap {
+  #[allow(non_snake_case)]
+  fn normal(&self, hit: &Hit) -> Direction {
+    let c = self.texture.color(hit.uv, hit.point);
+    let n = Vector3::new(
+      (2. * c.r - 1.) * self.strength,
+      (2. * c.g - 1.) * self.strength,
+      (2. * c.b - 1.).max(0.),
+    );
+
+    // Seen from behind, the bitangent turns over with the normal so that the
+    // frame keeps its handedness
+    let N = hit.normal;
+    let bitangent = if hit.front_face {
+      hit.bitangent
+    } else {
+      -hit.bitangent
+    };
+    let T = hit.tangent - N * N.dot(hit.tangent);
+    let B = bitangent - N * N.dot(bitangent);
+    if T.magnitude2() < f32::EPSILON || B.magnitude2() < f32::EPSILON {
+      return N;
+    }
+
+    let shading = T.normalize() * n.x + B.normalize() * n.y + N * n.z;
+    if shading.magnitude2() < f32::EPSILON {
+      N
+    } else {
+      shading.normalize()
+    }
+  }
+}
+
+/// Bump map: the surface is shaded as if it were raised by `height` world
+/// units along its normal, the height being a function of the hit point.
+#[derive(Clone)]
+pub struct BumpMap {
+  height: Arc<dyn Fn(Position) -> f32 + Send + Sync>,
+  strength: f32,
+  epsilon: f32,
+}
+
+impl BumpMap {
+  pub fn new(height: impl Fn(Position) -> f32 + Send + Sync + 'static) -> Self {
+    Self {
+      height: Arc::new(height),
+      strength: 1.,
+      epsilon: 1e-3,
+    }
+  }
+
+  /// Scales the height.
+  pub fn with_strength(mut self, strength: f32) -> Self {
+    self.strength = strength;
+    self
+  }
+
+  /// Step of the finite differences taking the slope of the height.
+  pub fn with_epsilon(mut self, epsilon: f32) -> Self {
+    self.epsilon = epsilon;
+    self
+  }
+}
+
+impl fmt::Debug for BumpMap {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("BumpMap")
+      .field("strength", &self.strength)
+      .field("epsilon", &self.epsilon)
+      .finish_non_exhaustive()
+  }
+}
+
+impl NormalMap for BumpMap {
+  #[allow(non_snake_case)]
+  fn normal(&self, hit: &Hit) -> Direction {
+    let P = hit.point;
+    let N = hit.normal;
+    let e = self.epsilon;
+
+    let slope = |axis: Vector3<f32>| {
+      ((self.height)(P + axis * e) - (self.height)(P - axis * e)) / (2. * e)
+    };
+    let gradient = Vector3::new(
+      slope(Vector3::unit_x()),
+      slope(Vector3::unit_y()),
+      slope(Vector3::unit_z()),
+    );
+
+    // Only the slope along the surface tilts the normal
+    let surface_gradient = gradient - N * N.dot(gradient);
+    let shading = N - surface_gradient * self.strength;
+
+    if shading.magnitude2() < f32::EPSILON {
+      N
+    } else {
+      shading.normalize()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use cgmath::Vector2;
+
+  use super::*;
+  use crate::primitive::{Color, Material};
+
+  #[test]
+  fn perturbs_back_faces_like_front_faces() {
+    let image = Image::new(1, 1, vec![Color::new(0.75, 0.6, 1.)]);
+    let normal_map = ImageNormalMap::new(image);
+    let material = Material::new(Color::WHITE);
+
+    let hit = |front_face: bool| Hit {
+      t: 1.,
+      point: Vector3::new(0., 0., 0.),
+      normal: if front_face {
+        Vector3::unit_z()
+      } else {
+        -Vector3::unit_z()
+      },
+      front_face,
+      uv: Vector2::new(0.5, 0.5),
+      tangent: Vector3::unit_x(),
+      bitangent: Vector3::unit_y(),
+      material: &material,
+    };
+
+    // The same tilt in the frame of the tangent, N × T and N
+    let tilt = |hit: &Hit| {
+      let shading = normal_map.normal(hit);
+      let normal = hit.normal;
+      [hit.tangent, normal.cross(hit.tangent), normal]
+        .map(|axis| shading.dot(axis))
+    };
+
+    let (front, back) = (tilt(&hit(true)), tilt(&hit(false)));
+    for (front, back) in front.iter().zip(back) {
+      assert!((front - back).abs() < 1e-5, "{front} != {back}");
+    }
+    assert!(front[0] > 0. && front[1] > 0.);
+  }
+}
@@ -0,0 +1,541 @@
+//! Wavefront OBJ/MTL loader.
+//!
+//! Supports positions, normals, texture coordinates, polygonal faces (fan
+//! triangulated), `g`/`o` groups and `usemtl`/`mtllib` materials. Every
+//! group/material run becomes one [`Mesh`]. Groups whose material is missing,
+//! or whose MTL file is, get a default gray.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use cgmath::{Vector2, Vector3};
+
+use crate::primitive::{Color, Material, Mesh};
+
+const DEFAULT_MATERIAL: Material =
+  Material::new(Color::from_srgb8(200, 200, 200));
+
+/// Syntax error in an OBJ or MTL file.
+#[derive(Debug)]
+pub struct ParseError {
+  pub path: PathBuf,
+  pub line: usize,
+  pub message: String,
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}:{}: {}", self.path.display(), self.line, self.message)
+  }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug)]
+pub struct ObjGroup {
+  pub name: String,
+  pub material_name: Option<String>,
+  pub mesh: Mesh,
+}
+
+/// Loads every group of an OBJ file, resolving `mtllib` relative to it.
+pub fn load_obj(path: impl AsRef<Path>) -> Result<Vec<ObjGroup>> {
+  let path = path.as_ref();
+  let source = fs::read_to_string(path)
+    .with_context(|| format!("failed to read {}", path.display()))?;
+
+  ObjParser::new(path).parse(&source)
+}
+
+/// Loads the materials of an MTL file by name.
+pub fn load_mtl(path: impl AsRef<Path>) -> Result<HashMap<String, Material>> {
+  let path = path.as_ref();
+  let source = fs::read_to_string(path)
+    .with_context(|| format!("failed to read {}", path.display()))?;
+
+  parse_mtl(path, &source)
+}
+
+/// Indices into the position, texture coordinate and normal lists.
+type FaceVertex = (usize, Option<usize>, Option<usize>);
+
+#[derive(Default)]
+struct GroupBuilder {
+  name: String,
+  material_name: Option<String>,
+  faces: Vec<[FaceVertex; 3]>,
+}
+
+struct ObjParser<'a> {
+  path: &'a Path,
+  line: usize,
+
+  positions: Vec<Vector3<f32>>,
+  normals: Vec<Vector3<f32>>,
+  uvs: Vec<Vector2<f32>>,
+  materials: HashMap<String, Material>,
+
+  current: GroupBuilder,
+  groups: Vec<GroupBuilder>,
+}
+
+impl<'a> ObjParser<'a> {
+  fn new(path: &'a Path) -> Self {
+    Self {
+      path,
+      line: 0,
+      positions: Vec::new(),
+      normals: Vec::new(),
+      uvs: Vec::new(),
+      materials: HashMap::new(),
+      current: GroupBuilder {
+        name: "default".into(),
+        ..Default::default()
+      },
+      groups: Vec::new(),
+    }
+  }
+
+  fn error(&self, message: impl Into<String>) -> ParseError {
+    ParseError {
+      path: self.path.to_path_buf(),
+      line: self.line,
+      message: message.into(),
+    }
+  }
+
+  fn parse(mut self, source: &str) -> Result<Vec<ObjGroup>> {
+    for (i, line) in source.lines().enumerate() {
+      self.line = i + 1;
+
+      let line = line.split('#').next().unwrap_or_default();
+      let mut tokens = line.split_whitespace();
+
+      let Some(keyword) = tokens.next() else {
+        continue;
+      };
+      let args: Vec<&str> = tokens.collect();
+
+      match keyword {
+        "v" => {
+          let v = self.floats(&args, 3)?;
+          self.positions.push(Vector3::new(v[0], v[1], v[2]));
+        }
+        "vn" => {
+          let v = self.floats(&args, 3)?;
+          self.normals.push(Vector3::new(v[0], v[1], v[2]));
+        }
+        "vt" => {
+          let uv = self.floats(&args, 1)?;
+          self.uvs.push(Vector2::new(uv[0], uv.get(1).copied().unwrap_or(0.)));
+        }
+        "f" => self.face(&args)?,
+        "g" | "o" => {
+          let name = if args.is_empty() {
+            "default".into()
+          } else {
+            args.join(" ")
+          };
+          let material_name = self.current.material_name.clone();
+          self.start_group(name, material_name);
+        }
+        "usemtl" => {
+          let name = args.join(" ");
+          let group_name = self.current.name.clone();
+          self.start_group(group_name, Some(name));
+        }
+        "mtllib" => {
+          for file in &args {
+            let mtl_path = self.path.with_file_name(file);
+            let Ok(source) = fs::read_to_string(&mtl_path) else {
+              continue;
+            };
+            let materials = parse_mtl(&mtl_path, &source)
+              .with_context(|| self.error(format!("in mtllib `{file}`")))?;
+            self.materials.extend(materials);
+          }
+        }
+        // Smoothing groups, lines, points, free-form geometry...
+        _ => {}
+      }
+    }
+
+    let current = std::mem::take(&mut self.current);
+    self.groups.push(current);
+
+    let groups = std::mem::take(&mut self.groups);
+    Ok(
+      groups
+        .into_iter()
+        .filter(|group| !group.faces.is_empty())
+        .map(|group| self.build(group))
+        .collect(),
+    )
+  }
+
+  fn floats(&self, args: &[&str], min: usize) -> Result<Vec<f32>, ParseError> {
+    if args.len() < min {
+      return Err(self.error(format!(
+        "expected at least {min} numbers, found {}",
+        args.len()
+      )));
+    }
+
+    args
+      .iter()
+      .map(|arg| {
+        arg
+          .parse::<f32>()
+          .map_err(|_| self.error(format!("invalid number `{arg}`")))
+      })
+      .collect()
+  }
+
+  fn start_group(&mut self, name: String, material_name: Option<String>) {
+    let previous = std::mem::replace(
+      &mut self.current,
+      GroupBuilder {
+        name,
+        material_name,
+        faces: Vec::new(),
+      },
+    );
+
+    if !previous.faces.is_empty() {
+      self.groups.push(previous);
+    }
+  }
+
+  fn face(&mut self, args: &[&str]) -> Result<(), ParseError> {
+    if args.len() < 3 {
+      return Err(self.error("a face needs at least 3 vertices"));
+    }
+
+    let vertices = args
+      .iter()
+      .map(|arg| self.face_vertex(arg))
+      .collect::<Result<Vec<_>, _>>()?;
+
+    // Fan triangulation, fine for the convex polygons exporters write
+    for i in 1..vertices.len() - 1 {
+      self.current.faces.push([vertices[0], vertices[i], vertices[i + 1]]);
+    }
+
+    Ok(())
+  }
+
+  /// Parses `p`, `p/t`, `p//n` or `p/t/n`.
+  fn face_vertex(&self, arg: &str) -> Result<FaceVertex, ParseError> {
+    let mut parts = arg.split('/');
+
+    let position = parts.next().unwrap_or_default();
+    let position = self.index(position, self.positions.len())?;
+
+    let uv = match parts.next() {
+      Some("") | None => None,
+      Some(uv) => Some(self.index(uv, self.uvs.len())?),
+    };
+
+    let normal = match parts.next() {
+      Some("") | None => None,
+      Some(normal) => Some(self.index(normal, self.normals.len())?),
+    };
+
+    Ok((position, uv, normal))
+  }
+
+  /// Resolves a 1-based (or negative, relative) index into a list of `len`.
+  fn index(&self, arg: &str, len: usize) -> Result<usize, ParseError> {
+    let index = arg
+      .parse::<isize>()
+      .map_err(|_| self.error(format!("invalid index `{arg}`")))?;
+
+    let resolved = match index {
+      1.. => index - 1,
+      ..=-1 => len as isize + index,
+      0 => -1,
+    };
+
+    if resolved < 0 || resolved >= len as isize {
+      return Err(self.error(format!("index {index} out of range")));
+    }
+
+    Ok(resolved as usize)
+  }
+
+  fn build(&self, group: GroupBuilder) -> ObjGroup {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut indices = Vec::new();
+    let mut vertex_indices: HashMap<FaceVertex, u32> = HashMap::new();
+
+    let has_normals = group.faces.iter().flatten().all(|v| v.2.is_some());
+    let has_uvs = group.faces.iter().flatten().all(|v| v.1.is_some());
+
+    for face in &group.faces {
+      let face = face.map(|vertex| {
+        *vertex_indices.entry(vertex).or_insert_with(|| {
+          let (p, t, n) = vertex;
+          positions.push(self.positions[p]);
+          if let (true, Some(n)) = (has_normals, n) {
+            normals.push(self.normals[n]);
+          }
+          if let (true, Some(t)) = (has_uvs, t) {
+            uvs.push(self.uvs[t]);
+          }
+          positions.len() as u32 - 1
+        })
+      });
+      indices.push(face);
+    }
+
+    let material = group
+      .material_name
+      .as_ref()
+      .and_then(|name| self.materials.get(name))
+      .cloned()
+      .unwrap_or(DEFAULT_MATERIAL);
+
+    let mut mesh = Mesh::new(positions, indices, material);
+    if has_normals {
+      mesh = mesh.with_normals(normals);
+    }
+    if has_uvs {
+      mesh = mesh.with_uvs(uvs);
+    }
+
+    ObjGroup {
+      name: group.name,
+      material_name: group.material_name,
+      mesh,
+    }
+  }
+}
+
+/// `Kd` maps to `color`, `Ns` to `specular`, and for the illumination models
+/// with ray traced reflection (`illum` 3 to 7) the mean of `Ks` to
+/// `reflective`. `d` (or `Tr`) and `Ni` map to `transparency` and
+/// `refractive_index`, and `Ke` to `emission`.
+fn parse_mtl(path: &Path, source: &str) -> Result<HashMap<String, Material>> {
+  let mut materials = HashMap::new();
+  let mut current: Option<(String, MtlBuilder)> = None;
+
+  for (i, line) in source.lines().enumerate() {
+    let error = |message: String| ParseError {
+      path: path.to_path_buf(),
+      line: i + 1,
+      message,
+    };
+    let floats = |args: &[&str], count: usize| {
+      if args.len() < count {
+        return Err(error(format!("expected {count} numbers")));
+      }
+      args[..count]
+        .iter()
+        .map(|arg| {
+          arg
+            .parse::<f32>()
+            .map_err(|_| error(format!("invalid number `{arg}`")))
+        })
+        .collect::<Result<Vec<_>, _>>()
+    };
+
+    let line = line.split('#').next().unwrap_or_default();
+    let mut tokens = line.split_whitespace();
+
+    let Some(keyword) = tokens.next() else {
+      continue;
+    };
+    let args: Vec<&str> = tokens.collect();
+
+    if keyword == "newmtl" {
+      if let Some((name, builder)) = current.take() {
+        materials.insert(name, builder.build());
+      }
+      current = Some((args.join(" "), MtlBuilder::default()));
+      continue;
+    }
+
+    let Some((_, builder)) = current.as_mut() else {
+      if matches!(
+        keyword,
+        "Kd" | "Ks" | "Ke" | "Ns" | "d" | "Tr" | "Ni" | "illum"
+      ) {
+        return Err(error(format!("`{keyword}` before `newmtl`")).into());
+      }
+      continue;
+    };
+
+    match keyword {
+      "Kd" => {
+        let kd = floats(&args, 3)?;
+        builder.diffuse = [kd[0], kd[1], kd[2]];
+      }
+      "Ks" => {
+        let ks = floats(&args, 3)?;
+        builder.specular = [ks[0], ks[1], ks[2]];
+      }
+      "Ke" => {
+        let ke = floats(&args, 3)?;
+        builder.emission = [ke[0], ke[1], ke[2]];
+      }
+      "Ns" => builder.shininess = floats(&args, 1)?[0],
+      "d" => builder.transparency = 1. - floats(&args, 1)?[0],
+      "Tr" => builder.transparency = floats(&args, 1)?[0],
+      "Ni" => builder.refractive_index = floats(&args, 1)?[0],
+      "illum" => {
+        builder.illum = args
+          .first()
+          .and_then(|arg| arg.parse().ok())
+          .ok_or_else(|| error("invalid illumination model".into()))?;
+      }
+      _ => {}
+    }
+  }
+
+  if let Some((name, builder)) = current {
+    materials.insert(name, builder.build());
+  }
+
+  Ok(materials)
+}
+
+struct MtlBuilder {
+  diffuse: [f32; 3],
+  specular: [f32; 3],
+  emission: [f32; 3],
+  shininess: f32,
+  transparency: f32,
+  refractive_index: f32,
+  illum: u32,
+}
+
+impl Default for MtlBuilder {
+  fn default() -> Self {
+    Self {
+      diffuse: [0.8, 0.8, 0.8],
+      specular: [0., 0., 0.],
+      emission: [0., 0., 0.],
+      shininess: 0.,
+      transparency: 0.,
+      refractive_index: 1.,
+      illum: 2,
+    }
+  }
+}
+
+impl MtlBuilder {
+  fn build(self) -> Material {
+    let [r, g, b] = self.diffuse;
+    let mut material = Material::new(Color::from_srgb(r, g, b).clamp());
+
+    if self.shininess > 0. {
+      material = material.with_specular(self.shininess);
+    }
+
+    let reflectivity = self.specular.iter().sum::<f32>() / 3.;
+    if (3..=7).contains(&self.illum) && reflectivity > 0. {
+      material = material.with_reflective(reflectivity.clamp(0., 1.));
+    }
+
+    if self.transparency > 0. {
+      material = material
+        .with_transparency(self.transparency.clamp(0., 1.))
+        .with_refractive_index(self.refractive_index);
+    }
+
+    let [r, g, b] = self.emission;
+    material.with_emission(Color::from_srgb(r, g, b))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const QUAD: &str = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vn 0 0 1
+";
+
+  fn parse(source: &str) -> Result<Vec<ObjGroup>> {
+    ObjParser::new(Path::new("test.obj")).parse(source)
+  }
+
+  #[test]
+  fn triangulates_polygons() {
+    let groups = parse(&format!("{QUAD}f 1//1 2//1 3//1 4//1\n")).unwrap();
+
+    let mesh = &groups[0].mesh;
+    assert_eq!(mesh.indices, [[0, 1, 2], [0, 2, 3]]);
+    assert_eq!(mesh.positions[3], Vector3::new(0., 1., 0.));
+    assert_eq!(mesh.normals.as_ref().map(Vec::len), Some(4));
+  }
+
+  #[test]
+  fn resolves_negative_indices() {
+    let relative = parse(&format!("{QUAD}f -4 -3 -2\n")).unwrap();
+    let absolute = parse(&format!("{QUAD}f 1 2 3\n")).unwrap();
+
+    assert_eq!(relative[0].mesh.positions, absolute[0].mesh.positions);
+    assert_eq!(relative[0].mesh.indices, absolute[0].mesh.indices);
+  }
+
+  #[test]
+  fn splits_groups_and_materials() {
+    let mut parser = ObjParser::new(Path::new("test.obj"));
+    parser.materials =
+      parse_mtl(Path::new("test.mtl"), "newmtl red\nKd 1 0 0\n").unwrap();
+    let source = format!(
+      "{QUAD}g first\nf 1 2 3\ng second\nf 1 3 4\nusemtl red\nf 2 3 4\n"
+    );
+    let groups = parser.parse(&source).unwrap();
+
+    let names: Vec<_> = groups
+      .iter()
+      .map(|group| (group.name.as_str(), group.material_name.as_deref()))
+      .collect();
+    assert_eq!(
+      names,
+      [("first", None), ("second", None), ("second", Some("red"))]
+    );
+    assert_eq!(groups[2].mesh.material.color, Color::new(1., 0., 0.));
+  }
+
+  #[test]
+  fn reports_file_and_line() {
+    let error = parse("v 0 0 0\nv 1 0 0\nv x 0 0\n").unwrap_err();
+    assert_eq!(error.to_string(), "test.obj:3: invalid number `x`");
+
+    let error = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n").unwrap_err();
+    assert_eq!(error.to_string(), "test.obj:4: index 4 out of range");
+
+    let error = parse("v 0 0 0\nf 1 1\n").unwrap_err();
+    assert_eq!(
+      error.to_string(),
+      "test.obj:2: a face needs at least 3 vertices"
+    );
+
+    let error = parse_mtl(Path::new("test.mtl"), "newmtl a\nKd 1 0\n");
+    assert_eq!(
+      error.unwrap_err().to_string(),
+      "test.mtl:2: expected 3 numbers"
+    );
+  }
+
+  #[test]
+  fn falls_back_to_default_material() {
+    let source = format!("mtllib missing.mtl\n{QUAD}usemtl unknown\nf 1 2 3\n");
+    let groups = parse(&source).unwrap();
+
+    assert_eq!(groups[0].material_name.as_deref(), Some("unknown"));
+    assert_eq!(groups[0].mesh.material.color, DEFAULT_MATERIAL.color);
+  }
+}
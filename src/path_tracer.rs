@@ -0,0 +1,260 @@
+//! Monte Carlo path tracing, an alternative to
+//! [`Scene::trace_ray`](crate::raytracer::Scene::trace_ray) that follows light
+//! as it bounces between diffuse surfaces too, for indirect lighting and
+//! color bleeding.
+
+use std::f32::consts::PI;
+
+use cgmath::{InnerSpace, Vector3};
+
+use crate::microfacet;
+use crate::primitive::Color;
+use crate::random::Rng;
+use crate::raytracer::{Reflectance, Scene};
+use crate::sampling::cosine_hemisphere;
+use crate::shape::orthonormal_basis;
+
+/// Estimates the light coming back along a ray by averaging random paths.
+///
+/// At every diffuse bounce the lights and emissive shapes of the scene are
+/// sampled directly (next-event estimation) and the path goes on in a
+/// cosine-weighted random direction, or for microfacet materials one
+/// following either the diffuse lobe or the highlight. Mirrors and
+/// transparent surfaces are chosen at random in the proportions
+/// [`Scene::trace_ray`] blends them in. Ambient lights and environment
+/// lighting are ignored, since indirect light takes their place, and rays
+/// leaving the scene pick up its background, so that an environment lights
+/// the scene through them.
+///
+/// Lights and emitters are always shadowed, even in scenes built without
+/// [`Scene::with_shadow`].
+///
+/// Lights keep the convention of [`Scene::trace_ray`], where a light of
+/// intensity `I` makes a white surface facing it send back `I`, as much as
+/// a whole sky of radiance `I` would. Bounced light, the background and
+/// emissive surfaces are radiance, reflected with the BRDF `albedo / π`, so
+/// a light delivers π times the irradiance of a physical light of the same
+/// intensity.
+///
+/// Past `roulette_depth` bounces paths are stopped at random, with
+/// surviving ones weighted up to keep the estimate unbiased, and none goes
+/// beyond `max_depth`. Paths are seeded by their ray and `seed`, so renders
+/// are reproducible.
+#[derive(Debug, Clone)]
+pub struct PathTracer {
+  samples: u32,
+  max_depth: u32,
+  roulette_depth: u32,
+  seed: u64,
+}
+
+impl Default for PathTracer {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl PathTracer {
+  pub fn new() -> Self {
+    Self {
+      samples: 16,
+      max_depth: 16,
+      roulette_depth: 3,
+      seed: 0,
+    }
+  }
+
+  /// Paths traced per call of [`PathTracer::trace`], on top of the samples
+  /// of the [`Renderer`](crate::render::Renderer).
+  pub fn with_samples(mut self, samples: u32) -> Self {
+    self.samples = samples.max(1);
+    self
+  }
+
+  pub fn with_max_depth(mut self, max_depth: u32) -> Self {
+    self.max_depth = max_depth;
+    self
+  }
+
+  pub fn with_roulette_depth(mut self, roulette_depth: u32) -> Self {
+    self.roulette_depth = roulette_depth;
+    self
+  }
+
+  pub fn with_seed(mut self, seed: u64) -> Self {
+    self.seed = seed;
+    self
+  }
+
+  /// Average color of the paths starting along the ray, from `min_t` on.
+  ///
+  /// * `O` - origin
+  /// * `D` - direction
+  #[allow(non_snake_case)]
+  pub fn trace(
+    &self,
+    scene: &Scene,
+    O: Vector3<f32>,
+    D: Vector3<f32>,
+    min_t: f32,
+  ) -> Color {
+    let [ox, oy, oz, dx, dy, dz] =
+      [O.x, O.y, O.z, D.x, D.y, D.z].map(|c| c.to_bits() as u64);
+    let stream =
+      (ox << 32 | oy) ^ (oz << 16) ^ (dx << 32 | dy).rotate_left(8) ^ dz;
+    let mut rng = Rng::with_stream(self.seed, stream);
+
+    let total: Color = (0..self.samples)
+      .map(|_| self.radiance(scene, O, D, min_t, &mut rng))
+      .sum();
+
+    total / self.samples as f32
+  }
+
+  /// Follows one path.
+  ///
+  /// * `O` - origin
+  /// * `D` - direction
+  #[allow(non_snake_case)]
+  fn radiance(
+    &self,
+    scene: &Scene,
+    mut O: Vector3<f32>,
+    mut D: Vector3<f32>,
+    mut min_t: f32,
+    rng: &mut Rng,
+  ) -> Color {
+    let mut radiance = Color::BLACK;
+    let mut throughput = Color::WHITE;
+    // Whether the light reaching the previous hit was not sampled directly
+    let mut mirrored = true;
+
+    for depth in 0..=self.max_depth {
+      let Some((hit, sampled)) =
+        scene.closest_intersection_emitter(O, D, min_t, f32::INFINITY)
+      else {
+        radiance += throughput * scene.background(D);
+        break;
+      };
+
+      let material = hit.material;
+      let P = hit.point;
+      let N = hit.shading_normal();
+
+      // Emitters lighting diffuse bounces were sampled along with the lights,
+      // unless they cannot be
+      if mirrored || !sampled {
+        radiance += throughput * material.emission;
+      }
+
+      let transparency = material.transparency.unwrap_or(0.);
+      // Microfacet surfaces reflect through their BRDF instead
+      let reflective = match material.pbr {
+        Some(_) => 0.,
+        None => material.reflective.unwrap_or(0.),
+      };
+      let choice = rng.next_f32();
+      mirrored = choice < transparency + (1. - transparency) * reflective;
+
+      D = if choice < transparency {
+        match scene.refract_ray(D, N, hit.front_face, material.refractive_index)
+        {
+          Some((T, fresnel)) if rng.next_f32() >= fresnel => T,
+          _ => scene.reflect_ray(-D, N),
+        }
+      } else if mirrored {
+        scene.reflect_ray(-D, N)
+      } else {
+        let reflectance =
+          Reflectance::new(material, material.color_at(hit.uv, P));
+        let V = -D.normalize();
+        // Light never goes through shapes, whether or not the scene casts
+        // shadows
+        radiance +=
+          throughput * scene.direct_lighting(P, N, V, reflectance, true);
+
+        let (tangent, bitangent) = orthonormal_basis(N);
+        let to_world =
+          |v: Vector3<f32>| tangent * v.x + bitangent * v.y + N * v.z;
+        let (u, v) = (rng.next_f32(), rng.next_f32());
+
+        match reflectance {
+          Reflectance::Phong { color, .. } => {
+            throughput *= color;
+            to_world(cosine_hemisphere(u, v))
+          }
+          Reflectance::Microfacet { base, pbr } => {
+            // Half of the paths follow the highlight, half the diffuse lobe
+            let alpha = microfacet::alpha(pbr.roughness);
+            let L = if rng.next_f32() < 0.5 {
+              let H = to_world(microfacet::sample_half_vector(u, v, alpha));
+              2. * V.dot(H) * H - V
+            } else {
+              to_world(cosine_hemisphere(u, v))
+            };
+
+            let n_dot_l = N.dot(L);
+            let pdf = 0.5 * microfacet::half_vector_pdf(N, V, L, alpha)
+              + 0.5 * n_dot_l.max(0.) / PI;
+            if n_dot_l <= 0. || pdf <= 0. {
+              break;
+            }
+
+            // `brdf` is π times the BRDF
+            throughput *=
+              microfacet::brdf(base, pbr, N, V, L) * (n_dot_l / (PI * pdf));
+            L
+          }
+        }
+      };
+
+      if depth >= self.roulette_depth {
+        let survival = throughput.max_channel().min(0.95);
+        if rng.next_f32() >= survival {
+          break;
+        }
+        throughput = throughput / survival;
+      }
+
+      O = P;
+      min_t = 0.001;
+    }
+
+    radiance
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::primitive::{Light, LightType, Material, Plane, Sphere};
+
+  #[test]
+  fn shadows_lights_without_scene_shadows() {
+    let floor = Plane {
+      point: Vector3::new(0., -1., 0.),
+      normal: Vector3::new(0., 1., 0.),
+      material: Material::new(Color::WHITE),
+    };
+    let occluder = Sphere {
+      center: Vector3::new(0., 1., 4.5),
+      radius: 0.5,
+      material: Material::new(Color::BLACK),
+    };
+    let lights = [Light::new(LightType::Point(Vector3::new(0., 3., 3.)), 1.)];
+
+    let path_tracer = PathTracer::new().with_samples(1).with_max_depth(0);
+    let lit = |scene: &Scene| {
+      // Hits the floor at (0, -1, 6), below the light and the occluder
+      let origin = Vector3::new(0., 0., 0.);
+      path_tracer.trace(scene, origin, Vector3::new(0., -0.5, 3.), 1.)
+    };
+
+    let open =
+      Scene::new(Color::BLACK).with_shapes(&[floor]).with_lights(&lights);
+    assert!(!lit(&open).is_black());
+
+    let occluded = open.with_shapes(&[occluder]);
+    assert!(lit(&occluded).is_black());
+  }
+}
@@ -1,26 +1,377 @@
-use cgmath::Vector3;
+use std::sync::{Arc, OnceLock};
 
-pub type Color = [f32; 4];
+use cgmath::{InnerSpace, Rad, Vector2, Vector3};
 
-#[derive(Debug)]
-pub struct Sphere {
-  pub center: Vector3<f32>,
-  pub radius: f32,
+use crate::bvh::Bvh;
+use crate::normal_map::NormalMap;
+use crate::texture::Texture;
+
+pub use crate::color::Color;
+
+/// `transparency` is the share of light passing through the surface, bent by
+/// `refractive_index` (1 for air, 1.33 for water, 1.5 for glass).
+///
+/// A `texture` takes the place of `color` wherever the surface is hit, and a
+/// `normal_map` bends the normal the surface is shaded with.
+///
+/// With `pbr` set the surface follows the microfacet model instead of Phong,
+/// `color` being its base color, and `specular` and `reflective` are
+/// ignored.
+///
+/// Surfaces with an `emission` glow with it on top of the light they reflect,
+/// and light the rest of the scene like area lights. Planes, which are
+/// unbounded, only light it through the bounces of the path tracer.
+#[derive(Debug, Clone)]
+pub struct Material {
   pub color: Color,
+  pub texture: Option<Arc<dyn Texture>>,
+  pub normal_map: Option<Arc<dyn NormalMap>>,
+  pub pbr: Option<Pbr>,
   pub specular: Option<f32>,
   pub reflective: Option<f32>,
+  pub transparency: Option<f32>,
+  pub refractive_index: f32,
+  pub emission: Color,
+}
+
+impl Material {
+  pub const fn new(color: Color) -> Self {
+    Self {
+      color,
+      texture: None,
+      normal_map: None,
+      pbr: None,
+      specular: None,
+      reflective: None,
+      transparency: None,
+      refractive_index: 1.,
+      emission: Color::BLACK,
+    }
+  }
+
+  pub const fn with_specular(mut self, specular: f32) -> Self {
+    self.specular = Some(specular);
+    self
+  }
+
+  pub const fn with_reflective(mut self, reflective: f32) -> Self {
+    self.reflective = Some(reflective);
+    self
+  }
+
+  pub const fn with_transparency(mut self, transparency: f32) -> Self {
+    self.transparency = Some(transparency);
+    self
+  }
+
+  pub const fn with_refractive_index(mut self, refractive_index: f32) -> Self {
+    self.refractive_index = refractive_index;
+    self
+  }
+
+  pub const fn with_pbr(mut self, metallic: f32, roughness: f32) -> Self {
+    self.pbr = Some(Pbr::new(metallic, roughness));
+    self
+  }
+
+  pub const fn with_emission(mut self, emission: Color) -> Self {
+    self.emission = emission;
+    self
+  }
+
+  /// Switches a Phong material to the microfacet model, see
+  /// [`Pbr::from_phong`].
+  pub fn to_pbr(mut self) -> Self {
+    if self.pbr.is_none() {
+      self.pbr = Some(Pbr::from_phong(self.specular, self.reflective));
+    }
+    self
+  }
+
+  pub fn with_texture(mut self, texture: Arc<dyn Texture>) -> Self {
+    self.texture = Some(texture);
+    self
+  }
+
+  pub fn with_normal_map(mut self, normal_map: Arc<dyn NormalMap>) -> Self {
+    self.normal_map = Some(normal_map);
+    self
+  }
+
+  /// Surface color at a hit, from the texture if there is one.
+  pub fn color_at(&self, uv: Vector2<f32>, point: Position) -> Color {
+    match &self.texture {
+      Some(texture) => texture.color(uv, point),
+      None => self.color,
+    }
+  }
+}
+
+/// Metallic-roughness parameters of the microfacet model, both in `0..=1`.
+///
+/// Metals reflect light tinted by their base color and have no diffuse
+/// part; dielectrics reflect about 4% of the light untinted. Rougher
+/// surfaces spread their highlights and blur their reflections.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pbr {
+  pub metallic: f32,
+  pub roughness: f32,
+}
+
+impl Pbr {
+  pub const fn new(metallic: f32, roughness: f32) -> Self {
+    Self {
+      metallic,
+      roughness,
+    }
+  }
+
+  /// Closest match for the Phong parameters of a material: the `specular`
+  /// exponent sets the roughness of a highlight of the same width, and the
+  /// `reflective` share of mirrored light turns into metalness.
+  pub fn from_phong(specular: Option<f32>, reflective: Option<f32>) -> Self {
+    // Blinn-Phong exponent n ~ 2 / alpha² - 2, with alpha = roughness²
+    let roughness = match specular {
+      Some(n) if n > 0. => (2. / (n + 2.)).sqrt().sqrt(),
+      _ => 1.,
+    };
+
+    Self::new(reflective.unwrap_or(0.).clamp(0., 1.), roughness)
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct Sphere {
+  pub center: Vector3<f32>,
+  pub radius: f32,
+  pub material: Material,
+}
+
+/// Infinite plane through `point`, facing `normal`.
+#[derive(Debug, Clone)]
+pub struct Plane {
+  pub point: Vector3<f32>,
+  pub normal: Vector3<f32>,
+  pub material: Material,
+}
+
+/// Plane bounded to a circle of `radius` around `center`.
+#[derive(Debug, Clone)]
+pub struct Disk {
+  pub center: Vector3<f32>,
+  pub normal: Vector3<f32>,
+  pub radius: f32,
+  pub material: Material,
+}
+
+#[derive(Debug, Clone)]
+pub struct Triangle {
+  pub vertices: [Vector3<f32>; 3],
+  pub material: Material,
+}
+
+/// Triangle mesh sharing vertices between faces through `indices`.
+///
+/// When `normals` is set it holds one normal per vertex and the surface is
+/// shaded smoothly by interpolating them. `uvs` likewise holds one texture
+/// coordinate per vertex.
+///
+/// Faces are indexed by a hierarchy built on the first intersection test.
+/// [`Scene::shape_mut`](crate::raytracer::Scene::shape_mut) drops it so that
+/// it is rebuilt after edits; geometry edited otherwise after rendering
+/// started needs a call to [`Shape::invalidate`](crate::shape::Shape).
+#[derive(Debug, Clone)]
+pub struct Mesh {
+  pub positions: Vec<Vector3<f32>>,
+  pub normals: Option<Vec<Vector3<f32>>>,
+  pub uvs: Option<Vec<Vector2<f32>>>,
+  pub indices: Vec<[u32; 3]>,
+  pub material: Material,
+  pub(crate) bvh: OnceLock<Bvh>,
+}
+
+impl Mesh {
+  pub fn new(
+    positions: Vec<Vector3<f32>>,
+    indices: Vec<[u32; 3]>,
+    material: Material,
+  ) -> Self {
+    Self {
+      positions,
+      normals: None,
+      uvs: None,
+      indices,
+      material,
+      bvh: OnceLock::new(),
+    }
+  }
+
+  pub fn with_normals(mut self, normals: Vec<Vector3<f32>>) -> Self {
+    assert_eq!(normals.len(), self.positions.len());
+    self.normals = Some(normals);
+    self
+  }
+
+  pub fn with_uvs(mut self, uvs: Vec<Vector2<f32>>) -> Self {
+    assert_eq!(uvs.len(), self.positions.len());
+    self.uvs = Some(uvs);
+    self
+  }
+
+  /// Computes per-vertex normals by averaging the area-weighted normals of
+  /// the faces around each vertex.
+  pub fn with_smooth_normals(self) -> Self {
+    let mut normals = vec![Vector3::new(0., 0., 0.); self.positions.len()];
+
+    for face in &self.indices {
+      let [a, b, c] = face.map(|i| self.positions[i as usize]);
+      // Not normalized: the magnitude is twice the face area
+      let normal = (b - a).cross(c - a);
+
+      for i in face {
+        normals[*i as usize] += normal;
+      }
+    }
+
+    let normals = normals
+      .into_iter()
+      .map(|n| {
+        if n.magnitude2() > 0. {
+          n.normalize()
+        } else {
+          n
+        }
+      })
+      .collect();
+
+    self.with_normals(normals)
+  }
+
+  pub fn triangle(&self, face: usize) -> [Vector3<f32>; 3] {
+    self.indices[face].map(|i| self.positions[i as usize])
+  }
 }
 
 pub type Position = Vector3<f32>;
 pub type Direction = Vector3<f32>;
 
+#[derive(Debug, Clone, Copy)]
 pub enum LightType {
   Ambient,
   Point(Position),
   Directional(Direction),
+  /// Point light shining down `direction`, at full intensity within
+  /// `inner_angle` of it and fading out smoothly up to `outer_angle`.
+  Spot {
+    position: Position,
+    direction: Direction,
+    inner_angle: Rad<f32>,
+    outer_angle: Rad<f32>,
+  },
+  /// Parallelogram spanned by `edge_u` and `edge_v` from `corner`, lit on
+  /// both sides. Sampled with `samples × samples` stratified shadow rays,
+  /// which soften the shadows into penumbrae.
+  RectArea {
+    corner: Position,
+    edge_u: Direction,
+    edge_v: Direction,
+    samples: u32,
+  },
+  /// Sphere sampled like [`LightType::RectArea`], over the cap of it the
+  /// lit point sees, or over all of it from inside.
+  SphereArea {
+    center: Position,
+    radius: f32,
+    samples: u32,
+  },
+}
+
+/// How the intensity of point and spot lights decreases with the distance
+/// `d` to the lit point.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Attenuation {
+  #[default]
+  None,
+  /// `1 / d²`, as physical lights do.
+  InverseSquare,
+  /// `1 / (constant + linear * d + quadratic * d²)`
+  Polynomial {
+    constant: f32,
+    linear: f32,
+    quadratic: f32,
+  },
+}
+
+impl Attenuation {
+  pub fn factor(self, distance: f32) -> f32 {
+    let denominator = match self {
+      Self::None => return 1.,
+      Self::InverseSquare => distance * distance,
+      Self::Polynomial {
+        constant,
+        linear,
+        quadratic,
+      } => constant + linear * distance + quadratic * distance * distance,
+    };
+
+    if denominator > 0. {
+      1. / denominator
+    } else {
+      1.
+    }
+  }
 }
 
+/// `intensity` is per channel, so lights can be tinted. `attenuation` and
+/// `range` affect every light with a position, i.e. all but ambient and
+/// directional ones.
+#[derive(Debug, Clone, Copy)]
 pub struct Light {
   pub ty: LightType,
-  pub intensity: f32,
+  pub intensity: Color,
+  pub attenuation: Attenuation,
+  /// Distance at which the light has faded out completely.
+  pub range: Option<f32>,
+}
+
+impl Light {
+  /// White light.
+  pub const fn new(ty: LightType, intensity: f32) -> Self {
+    Self::colored(ty, Color::gray(intensity))
+  }
+
+  pub const fn colored(ty: LightType, intensity: Color) -> Self {
+    Self {
+      ty,
+      intensity,
+      attenuation: Attenuation::None,
+      range: None,
+    }
+  }
+
+  pub const fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+    self.attenuation = attenuation;
+    self
+  }
+
+  pub const fn with_range(mut self, range: f32) -> Self {
+    self.range = Some(range);
+    self
+  }
+
+  /// Share of `intensity` reaching a point `distance` away. Near `range`
+  /// the light is windowed down smoothly rather than cut off.
+  pub fn attenuation_at(&self, distance: f32) -> f32 {
+    let window = match self.range {
+      Some(range) if distance >= range => return 0.,
+      Some(range) => {
+        let ratio = distance / range;
+        let ratio4 = ratio * ratio * ratio * ratio;
+        (1. - ratio4) * (1. - ratio4)
+      }
+      None => 1.,
+    };
+
+    self.attenuation.factor(distance) * window
+  }
 }
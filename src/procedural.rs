@@ -0,0 +1,222 @@
+//! Textures computed from the world-space hit point rather than looked up
+//! in an image, so they need no UV mapping and never run out of resolution.
+
+use cgmath::{InnerSpace, Vector2};
+
+use crate::noise::Noise;
+use crate::primitive::{Color, Direction, Position};
+use crate::texture::Texture;
+
+/// 3D checkerboard of cubes with sides `size`.
+#[derive(Debug, Clone)]
+pub struct Checker {
+  pub even: Color,
+  pub odd: Color,
+  pub size: f32,
+}
+
+impl Texture for Checker {
+  fn color(&self, _uv: Vector2<f32>, point: Position) -> Color {
+    let cell = point / self.size;
+    let sum = cell.x.floor() + cell.y.floor() + cell.z.floor();
+
+    if sum.rem_euclid(2.) < 1. {
+      self.even
+    } else {
+      self.odd
+    }
+  }
+}
+
+/// Slabs of `width` alternating along `direction`.
+#[derive(Debug, Clone)]
+pub struct Stripes {
+  pub even: Color,
+  pub odd: Color,
+  pub direction: Direction,
+  pub width: f32,
+}
+
+impl Texture for Stripes {
+  fn color(&self, _uv: Vector2<f32>, point: Position) -> Color {
+    let distance = point.dot(self.direction.normalize()) / self.width;
+
+    if distance.floor().rem_euclid(2.) < 1. {
+      self.even
+    } else {
+      self.odd
+    }
+  }
+}
+
+/// Linear blend from `from` at `start` to `to` at `end`, constant beyond.
+#[derive(Debug, Clone)]
+pub struct Gradient {
+  pub from: Color,
+  pub to: Color,
+  pub start: Position,
+  pub end: Position,
+}
+
+impl Texture for Gradient {
+  fn color(&self, _uv: Vector2<f32>, point: Position) -> Color {
+    let axis = self.end - self.start;
+    let t = (point - self.start).dot(axis) / axis.magnitude2();
+
+    self.from.lerp(self.to, t.clamp(0., 1.))
+  }
+}
+
+/// How the octaves of a [`NoiseTexture`] are summed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Fractal {
+  #[default]
+  Fbm,
+  Turbulence,
+}
+
+/// Blends `low` and `high` by noise with features of about `scale` units.
+#[derive(Debug, Clone)]
+pub struct NoiseTexture {
+  pub noise: Noise,
+  pub low: Color,
+  pub high: Color,
+  pub scale: f32,
+  pub octaves: u32,
+  pub fractal: Fractal,
+}
+
+impl NoiseTexture {
+  pub fn new(noise: Noise, low: Color, high: Color) -> Self {
+    Self {
+      noise,
+      low,
+      high,
+      scale: 1.,
+      octaves: 4,
+      fractal: Fractal::Fbm,
+    }
+  }
+
+  pub fn with_scale(mut self, scale: f32) -> Self {
+    self.scale = scale;
+    self
+  }
+
+  pub fn with_octaves(mut self, octaves: u32) -> Self {
+    self.octaves = octaves;
+    self
+  }
+
+  pub fn with_fractal(mut self, fractal: Fractal) -> Self {
+    self.fractal = fractal;
+    self
+  }
+}
+
+impl Texture for NoiseTexture {
+  fn color(&self, _uv: Vector2<f32>, point: Position) -> Color {
+    let p = point / self.scale;
+    let t = match self.fractal {
+      Fractal::Fbm => 0.5 + 0.5 * self.noise.fbm(p, self.octaves),
+      Fractal::Turbulence => self.noise.turbulence(p, self.octaves),
+    };
+
+    self.low.lerp(self.high, t.clamp(0., 1.))
+  }
+}
+
+/// Veins along `x`, bent by turbulence.
+#[derive(Debug, Clone)]
+pub struct Marble {
+  pub noise: Noise,
+  pub base: Color,
+  pub vein: Color,
+  /// Distance between veins.
+  pub period: f32,
+  /// How far turbulence pushes the veins around.
+  pub distortion: f32,
+  pub octaves: u32,
+}
+
+impl Marble {
+  /// White marble with grey veins.
+  pub fn new(seed: u64) -> Self {
+    Self {
+      noise: Noise::perlin(seed),
+      base: Color::from_srgb8(235, 232, 225),
+      vein: Color::from_srgb8(90, 90, 100),
+      period: 0.5,
+      distortion: 4.,
+      octaves: 6,
+    }
+  }
+
+  pub fn with_colors(mut self, base: Color, vein: Color) -> Self {
+    self.base = base;
+    self.vein = vein;
+    self
+  }
+}
+
+impl Texture for Marble {
+  fn color(&self, _uv: Vector2<f32>, point: Position) -> Color {
+    let p = point / self.period;
+    let phase = p.x + self.distortion * self.noise.turbulence(p, self.octaves);
+
+    // Sharpened so veins stay thin
+    let t = (0.5 + 0.5 * (phase * std::f32::consts::PI).sin()).powf(8.);
+    self.base.lerp(self.vein, t)
+  }
+}
+
+/// Growth rings around the vertical axis through `origin`, wobbled by
+/// noise.
+#[derive(Debug, Clone)]
+pub struct Wood {
+  pub noise: Noise,
+  pub origin: Position,
+  pub light: Color,
+  pub dark: Color,
+  /// Distance between rings.
+  pub ring_width: f32,
+  /// How much noise warps the rings, in rings.
+  pub distortion: f32,
+}
+
+impl Wood {
+  /// Pale pine.
+  pub fn new(seed: u64) -> Self {
+    Self {
+      noise: Noise::perlin(seed),
+      origin: Position::new(0., 0., 0.),
+      light: Color::from_srgb8(222, 184, 135),
+      dark: Color::from_srgb8(150, 100, 60),
+      ring_width: 0.08,
+      distortion: 1.5,
+    }
+  }
+
+  pub fn with_origin(mut self, origin: Position) -> Self {
+    self.origin = origin;
+    self
+  }
+
+  pub fn with_colors(mut self, light: Color, dark: Color) -> Self {
+    self.light = light;
+    self.dark = dark;
+    self
+  }
+}
+
+impl Texture for Wood {
+  fn color(&self, _uv: Vector2<f32>, point: Position) -> Color {
+    let p = point - self.origin;
+    let radius = Vector2::new(p.x, p.z).magnitude() / self.ring_width;
+    let rings = radius + self.distortion * self.noise.fbm(p * 2., 3);
+
+    // Light early wood fading into a darker late wood edge
+    let t = rings.rem_euclid(1.).powf(3.);
+    self.light.lerp(self.dark, t)
+  }
+}
@@ -0,0 +1,36 @@
+//! Small deterministic random number generator, so renders are reproducible
+//! from a seed without depending on thread scheduling.
+
+/// SplitMix64 generator.
+#[derive(Debug, Clone)]
+pub struct Rng {
+  state: u64,
+}
+
+impl Rng {
+  pub fn new(seed: u64) -> Self {
+    Self { state: seed }
+  }
+
+  /// Independent generator for one `stream` (a pixel, a light...) of a
+  /// seeded computation.
+  pub fn with_stream(seed: u64, stream: u64) -> Self {
+    Self::new(mix(seed ^ mix(stream)))
+  }
+
+  pub fn next_u64(&mut self) -> u64 {
+    self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    mix(self.state)
+  }
+
+  /// Uniform in `[0, 1)`.
+  pub fn next_f32(&mut self) -> f32 {
+    (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+  }
+}
+
+fn mix(mut z: u64) -> u64 {
+  z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+  z ^ (z >> 31)
+}
@@ -1,26 +1,121 @@
-use cgmath::{InnerSpace, Vector3};
+use std::any::Any;
+use std::f32::consts::PI;
+use std::sync::{Arc, OnceLock};
 
-use crate::primitive::{Color, Light, LightType, Sphere};
+use cgmath::{InnerSpace, Vector3};
 
-pub struct Scene<'a> {
-  pub spheres: &'a [Sphere],
+use crate::bvh::{Aabb, Bvh};
+use crate::environment::{Environment, Irradiance};
+use crate::microfacet;
+use crate::primitive::{
+  Color, Light, LightType, Material, Pbr, Position, Sphere,
+};
+use crate::random::Rng;
+use crate::sampling::{uniform_sphere, Sampler};
+use crate::shape::{sample_sphere_cap, Hit, Shape};
+
+/// Stable reference to a shape of a [`Scene`], valid until it is removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ObjectHandle(u64);
+
+/// Stable reference to a light of a [`Scene`], valid until it is removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LightHandle(u64);
+
+/// Owns its shapes and lights, which can be added, edited and removed through
+/// handles. The BVH over the shapes is rebuilt on the first ray traced after
+/// a change.
+///
+/// Shapes with an emissive material light the scene too, each sampled with
+/// `emission_samples × emission_samples` shadow rays.
+///
+/// Rays leaving the scene pick up the color of its environment, or
+/// `background_color` without one. With `environment_lighting` the
+/// environment also lights the scene diffusely, in place of the ambient
+/// lights, which are then ignored: like an ambient light varying with the
+/// direction surfaces face.
+pub struct Scene {
   pub background_color: Color,
-  pub lights: &'a [Light],
   pub shadow: bool,
+  pub emission_samples: u32,
+  pub environment_lighting: bool,
+  environment: Option<Arc<dyn Environment>>,
+  irradiance: OnceLock<Irradiance>,
+  // Both sorted by handle, since handles only grow
+  shapes: Vec<(ObjectHandle, Box<dyn Shape>)>,
+  lights: Vec<(LightHandle, Light)>,
+  next_handle: u64,
+  use_bvh: bool,
+  acceleration: OnceLock<Acceleration>,
+}
+
+/// Bounded shapes go into the BVH, unbounded ones are tested one by one.
+/// Emitters are the shapes sampled as lights, in increasing order.
+struct Acceleration {
+  bvh: Bvh,
+  bounded: Vec<usize>,
+  unbounded: Vec<usize>,
+  emitters: Vec<usize>,
+}
+
+impl Acceleration {
+  fn new(shapes: &[(ObjectHandle, Box<dyn Shape>)], use_bvh: bool) -> Self {
+    let mut boxes: Vec<Aabb> = Vec::new();
+    let mut bounded = Vec::new();
+    let mut unbounded = Vec::new();
+    let mut emitters = Vec::new();
+
+    for (i, (_, shape)) in shapes.iter().enumerate() {
+      if !shape.emission().is_black() {
+        emitters.push(i);
+      }
+
+      match shape.bounding_box().filter(|_| use_bvh) {
+        Some(bounds) => {
+          boxes.push(bounds);
+          bounded.push(i);
+        }
+        None => unbounded.push(i),
+      }
+    }
+
+    Self {
+      bvh: Bvh::build(&boxes),
+      bounded,
+      unbounded,
+      emitters,
+    }
+  }
 }
 
-impl<'a> Scene<'a> {
-  pub fn new(spheres: &'a [Sphere], background_color: Color) -> Self {
+impl Scene {
+  pub fn new(background_color: Color) -> Self {
     Self {
-      spheres,
       background_color,
-      lights: &[],
       shadow: false,
+      emission_samples: 4,
+      environment_lighting: false,
+      environment: None,
+      irradiance: OnceLock::new(),
+      shapes: Vec::new(),
+      lights: Vec::new(),
+      next_handle: 0,
+      use_bvh: true,
+      acceleration: OnceLock::new(),
     }
   }
 
-  pub fn with_lights(mut self, lights: &'a [Light]) -> Self {
-    self.lights = lights;
+  pub fn with_shapes<S: Shape + Clone>(mut self, shapes: &[S]) -> Self {
+    for shape in shapes {
+      self.add_shape(shape.clone());
+    }
+    self
+  }
+
+  pub fn with_lights(mut self, lights: &[Light]) -> Self {
+    for light in lights {
+      self.add_light(*light);
+    }
     self
   }
 
@@ -29,105 +124,561 @@ impl<'a> Scene<'a> {
     self
   }
 
+  pub fn with_emission_samples(mut self, samples: u32) -> Self {
+    self.emission_samples = samples;
+    self
+  }
+
+  pub fn with_environment(mut self, environment: Arc<dyn Environment>) -> Self {
+    self.set_environment(Some(environment));
+    self
+  }
+
+  /// Lights the scene with its environment instead of its ambient lights,
+  /// see [`Scene`].
+  pub fn with_environment_lighting(mut self) -> Self {
+    self.environment_lighting = true;
+    self
+  }
+
+  pub fn set_environment(&mut self, environment: Option<Arc<dyn Environment>>) {
+    self.environment = environment;
+    self.irradiance.take();
+  }
+
+  pub fn environment(&self) -> Option<&dyn Environment> {
+    self.environment.as_deref()
+  }
+
+  /// Color seen along a ray leaving the scene in direction `D`.
+  #[allow(non_snake_case)]
+  pub fn background(&self, D: Vector3<f32>) -> Color {
+    match &self.environment {
+      Some(environment) => environment.color(D),
+      None => self.background_color,
+    }
+  }
+
+  /// Tests every shape against every ray instead of traversing a BVH.
+  pub fn without_bvh(mut self) -> Self {
+    self.use_bvh = false;
+    self.acceleration.take();
+    self
+  }
+
+  fn next_handle(&mut self) -> u64 {
+    self.next_handle += 1;
+    self.next_handle
+  }
+
+  pub fn add_shape(&mut self, shape: impl Shape) -> ObjectHandle {
+    let handle = ObjectHandle(self.next_handle());
+    self.shapes.push((handle, Box::new(shape)));
+    self.acceleration.take();
+    handle
+  }
+
+  pub fn add_sphere(&mut self, sphere: Sphere) -> ObjectHandle {
+    self.add_shape(sphere)
+  }
+
+  pub fn add_light(&mut self, light: Light) -> LightHandle {
+    let handle = LightHandle(self.next_handle());
+    self.lights.push((handle, light));
+    handle
+  }
+
+  /// Removes a shape, returning it if it was still in the scene.
+  pub fn remove(&mut self, handle: ObjectHandle) -> Option<Box<dyn Shape>> {
+    let i = self.shape_index(handle)?;
+    self.acceleration.take();
+    Some(self.shapes.remove(i).1)
+  }
+
+  pub fn remove_light(&mut self, handle: LightHandle) -> Option<Light> {
+    let i = self.light_index(handle)?;
+    Some(self.lights.remove(i).1)
+  }
+
+  pub fn shape(&self, handle: ObjectHandle) -> Option<&dyn Shape> {
+    let i = self.shape_index(handle)?;
+    Some(self.shapes[i].1.as_ref())
+  }
+
+  /// Mutable access to a shape of type `S`, e.g. to move a [`Sphere`]. The
+  /// caches of the scene and of the shape are rebuilt on the next ray.
+  pub fn shape_mut<S: Shape>(
+    &mut self,
+    handle: ObjectHandle,
+  ) -> Option<&mut S> {
+    let i = self.shape_index(handle)?;
+    self.acceleration.take();
+    self.shapes[i].1.invalidate();
+    let shape: &mut dyn Any = self.shapes[i].1.as_mut();
+    shape.downcast_mut()
+  }
+
+  pub fn light(&self, handle: LightHandle) -> Option<&Light> {
+    let i = self.light_index(handle)?;
+    Some(&self.lights[i].1)
+  }
+
+  pub fn light_mut(&mut self, handle: LightHandle) -> Option<&mut Light> {
+    let i = self.light_index(handle)?;
+    Some(&mut self.lights[i].1)
+  }
+
+  pub fn shapes(&self) -> impl Iterator<Item = (ObjectHandle, &dyn Shape)> {
+    self.shapes.iter().map(|(handle, shape)| (*handle, shape.as_ref()))
+  }
+
+  pub fn lights(&self) -> impl Iterator<Item = (LightHandle, &Light)> {
+    self.lights.iter().map(|(handle, light)| (*handle, light))
+  }
+
+  fn shape_index(&self, handle: ObjectHandle) -> Option<usize> {
+    self.shapes.binary_search_by_key(&handle, |(h, _)| *h).ok()
+  }
+
+  fn light_index(&self, handle: LightHandle) -> Option<usize> {
+    self.lights.binary_search_by_key(&handle, |(h, _)| *h).ok()
+  }
+
+  fn acceleration(&self) -> &Acceleration {
+    self
+      .acceleration
+      .get_or_init(|| Acceleration::new(&self.shapes, self.use_bvh))
+  }
+
+  /// Light reflected towards the viewer by a point, which the ambient
+  /// lights reach diffusely only.
+  ///
   /// * `P` - point
   /// * `N` - normal
   /// * `V` - view
-  #[allow(non_snake_case, unused_assignments)]
+  #[allow(non_snake_case)]
   fn compute_lighting(
     &self,
     P: Vector3<f32>,
     N: Vector3<f32>,
     V: Vector3<f32>,
-    specular: Option<f32>,
-  ) -> f32 {
-    let mut intensity = 0.0;
+    reflectance: Reflectance,
+  ) -> Color {
+    let ambient = self.ambient_light(N) * reflectance.diffuse(N, V);
+    ambient + self.direct_lighting(P, N, V, reflectance, self.shadow)
+  }
+
+  /// Light reaching a surface facing `N` from all around: from the
+  /// environment with environment lighting, from the ambient lights
+  /// otherwise.
+  ///
+  /// * `N` - normal
+  #[allow(non_snake_case)]
+  fn ambient_light(&self, N: Vector3<f32>) -> Color {
+    match &self.environment {
+      Some(environment) if self.environment_lighting => {
+        let irradiance =
+          self.irradiance.get_or_init(|| Irradiance::new(environment.as_ref()));
+        irradiance.at(N)
+      }
+      _ => self
+        .lights
+        .iter()
+        .filter(|(_, light)| matches!(light.ty, LightType::Ambient))
+        .map(|(_, light)| light.intensity)
+        .sum(),
+    }
+  }
 
-    for light in self.lights {
-      let (L, t_max) = match light.ty {
-        LightType::Ambient => {
-          intensity += light.intensity;
+  /// Light reflected towards the viewer from every light but the ambient
+  /// ones, and from the emissive shapes, blocked by the shapes in between
+  /// when `shadow` is set.
+  ///
+  /// * `P` - point
+  /// * `N` - normal
+  /// * `V` - view
+  #[allow(non_snake_case)]
+  pub(crate) fn direct_lighting(
+    &self,
+    P: Vector3<f32>,
+    N: Vector3<f32>,
+    V: Vector3<f32>,
+    reflectance: Reflectance,
+    shadow: bool,
+  ) -> Color {
+    let mut intensity = Color::BLACK;
+
+    for (handle, light) in &self.lights {
+      let (L, t_max, falloff) = match light.ty {
+        LightType::Ambient => continue,
+        LightType::Point(position) => {
+          let L = position - P;
+          (L, 1., light.attenuation_at(L.magnitude()))
+        }
+        LightType::Directional(direction) => (direction, f32::INFINITY, 1.),
+        LightType::Spot {
+          position,
+          direction,
+          inner_angle,
+          outer_angle,
+        } => {
+          let L = position - P;
+          let cos_angle = -L.normalize().dot(direction.normalize());
+          let falloff =
+            smoothstep(outer_angle.0.cos(), inner_angle.0.cos(), cos_angle);
+          (L, 1., falloff * light.attenuation_at(L.magnitude()))
+        }
+        LightType::RectArea {
+          corner,
+          edge_u,
+          edge_v,
+          samples,
+        } => {
+          let sample_points = self
+            .area_samples(handle.0, P, samples)
+            .into_iter()
+            .map(|(u, v)| corner + edge_u * u + edge_v * v);
+          intensity += self.illuminate_area(
+            light,
+            sample_points,
+            P,
+            N,
+            V,
+            reflectance,
+            shadow,
+          );
+          continue;
+        }
+        LightType::SphereArea {
+          center,
+          radius,
+          samples,
+        } => {
+          // From inside, all of the sphere shines on the point
+          let sample_points = self
+            .area_samples(handle.0, P, samples)
+            .into_iter()
+            .map(|(u, v)| match sample_sphere_cap(center, radius, P, u, v) {
+              Some((point, _)) => point,
+              None => center + uniform_sphere(u, v) * radius,
+            });
+          intensity += self.illuminate_area(
+            light,
+            sample_points,
+            P,
+            N,
+            V,
+            reflectance,
+            shadow,
+          );
           continue;
         }
-        LightType::Point(position) => (position - P, 1.),
-        LightType::Directional(direction) => (direction, f32::INFINITY),
       };
 
-      //
-      // Shadow check
-      //
+      if falloff > 0. {
+        let light_intensity = light.intensity * falloff;
+        intensity += self.illuminate(
+          P,
+          N,
+          V,
+          reflectance,
+          L,
+          t_max,
+          light_intensity,
+          shadow,
+        );
+      }
+    }
 
-      if self.shadow {
-        let blocker = self.closest_intersection(P, L, 0.001, t_max);
+    for &i in &self.acceleration().emitters {
+      let (handle, shape) = &self.shapes[i];
+      let samples = self.area_samples(handle.0, P, self.emission_samples);
 
-        if blocker.is_some() {
+      // Seen under a small solid angle Ω, emission E lights like a point
+      // light of intensity E Ω / π
+      let share = shape.emission() / (PI * samples.len().max(1) as f32);
+
+      for (u, v) in samples {
+        let Some((point, solid_angle)) = shape.sample_towards(P, u, v) else {
           continue;
-        }
+        };
+
+        // Stop shadow rays short of the emitter itself
+        intensity += self.illuminate(
+          P,
+          N,
+          V,
+          reflectance,
+          point - P,
+          0.999,
+          share * solid_angle,
+          shadow,
+        );
       }
+    }
 
-      //
-      // Diffuse reflection
-      //
+    intensity
+  }
 
-      let n_dot_l = N.dot(L);
+  /// Diffuse and specular reflection of the light arriving at `P` from
+  /// `P + L`, or from direction `L` when `t_max` is infinite, once shadows
+  /// are accounted for if `shadow` is set.
+  ///
+  /// * `P` - point
+  /// * `N` - normal
+  /// * `V` - view
+  /// * `L` - light
+  #[allow(non_snake_case, clippy::too_many_arguments)]
+  fn illuminate(
+    &self,
+    P: Vector3<f32>,
+    N: Vector3<f32>,
+    V: Vector3<f32>,
+    reflectance: Reflectance,
+    L: Vector3<f32>,
+    t_max: f32,
+    mut light_intensity: Color,
+    shadow: bool,
+  ) -> Color {
+    let mut intensity = Color::BLACK;
 
-      if n_dot_l > 0.0 {
-        intensity +=
-          light.intensity * n_dot_l / (N.magnitude() * L.magnitude());
-      }
+    //
+    // Shadow check
+    //
 
-      //
-      // Specular reflection
-      //
+    if shadow {
+      light_intensity *= self.shadow_transmittance(P, L, 0.001, t_max);
 
-      if let Some(specular) = specular {
-        let R = self.reflect_ray(L, N);
-        let r_dot_v = R.dot(V);
+      if light_intensity.is_black() {
+        return intensity;
+      }
+    }
 
-        if r_dot_v > 0.0 {
-          intensity += light.intensity
-            * (r_dot_v / (R.magnitude() * V.magnitude())).powf(specular);
+    let specular = match reflectance {
+      Reflectance::Phong { specular, .. } => specular,
+      Reflectance::Microfacet { base, pbr } => {
+        let n_dot_l = N.dot(L) / (N.magnitude() * L.magnitude());
+        if n_dot_l <= 0. {
+          return intensity;
         }
+        return light_intensity
+          * microfacet::brdf(base, pbr, N, V, L)
+          * n_dot_l;
       }
+    };
+
+    //
+    // Diffuse reflection
+    //
+
+    let n_dot_l = N.dot(L);
+
+    if n_dot_l > 0.0 {
+      intensity += light_intensity * n_dot_l / (N.magnitude() * L.magnitude());
     }
 
-    intensity
+    //
+    // Specular reflection
+    //
+
+    if let Some(specular) = specular {
+      let R = self.reflect_ray(L, N);
+      let r_dot_v = R.dot(V);
+
+      if r_dot_v > 0.0 {
+        intensity += light_intensity
+          * (r_dot_v / (R.magnitude() * V.magnitude())).powf(specular);
+      }
+    }
+
+    intensity * reflectance.diffuse(N, V)
+  }
+
+  /// Shares the intensity of an area light between its sample points, each
+  /// lighting `P` like a point light with its own shadow ray.
+  #[allow(non_snake_case, clippy::too_many_arguments)]
+  fn illuminate_area(
+    &self,
+    light: &Light,
+    sample_points: impl ExactSizeIterator<Item = Position>,
+    P: Vector3<f32>,
+    N: Vector3<f32>,
+    V: Vector3<f32>,
+    reflectance: Reflectance,
+    shadow: bool,
+  ) -> Color {
+    let share = light.intensity / sample_points.len().max(1) as f32;
+
+    sample_points
+      .map(|point| {
+        let L = point - P;
+        let light_intensity = share * light.attenuation_at(L.magnitude());
+        self.illuminate(P, N, V, reflectance, L, 1., light_intensity, shadow)
+      })
+      .sum()
+  }
+
+  /// Stratified sample positions in `[0, 1)²`, seeded by the handle of the
+  /// light or shape and the lit point so that renders stay reproducible.
+  #[allow(non_snake_case)]
+  fn area_samples(
+    &self,
+    seed: u64,
+    P: Vector3<f32>,
+    samples: u32,
+  ) -> Vec<(f32, f32)> {
+    let [x, y, z] = [P.x, P.y, P.z].map(|c| c.to_bits() as u64);
+    let stream = (x << 32 | y) ^ z.rotate_left(16);
+    let mut rng = Rng::with_stream(seed, stream);
+
+    Sampler::Stratified(samples.max(1)).samples(&mut rng)
   }
 
   /// * `R` - ray
   /// * `N` - normal
   #[allow(non_snake_case)]
-  fn reflect_ray(&self, R: Vector3<f32>, N: Vector3<f32>) -> Vector3<f32> {
+  pub(crate) fn reflect_ray(
+    &self,
+    R: Vector3<f32>,
+    N: Vector3<f32>,
+  ) -> Vector3<f32> {
     2. * N * N.dot(R) - R
   }
 
+  /// Bends `D` through a surface with refractive index `ior` using Snell's
+  /// law. Returns the refracted direction and the share of light reflected
+  /// instead (Schlick's approximation of the Fresnel term), or `None` past
+  /// the critical angle.
+  ///
+  /// * `D` - direction
+  /// * `N` - normal, facing `D`
+  /// * `entering` - whether `D` comes from outside the surface
+  #[allow(non_snake_case)]
+  pub(crate) fn refract_ray(
+    &self,
+    D: Vector3<f32>,
+    N: Vector3<f32>,
+    entering: bool,
+    ior: f32,
+  ) -> Option<(Vector3<f32>, f32)> {
+    let D = D.normalize();
+    let eta = if entering { 1. / ior } else { ior };
+
+    let cos_i = -D.dot(N);
+    let sin2_t = eta * eta * (1. - cos_i * cos_i);
+
+    if sin2_t > 1. {
+      return None;
+    }
+
+    let cos_t = (1. - sin2_t).sqrt();
+    let T = eta * D + (eta * cos_i - cos_t) * N;
+
+    // The angle on the less dense side
+    let cos = if entering { cos_i } else { cos_t };
+    let r0 = ((1. - ior) / (1. + ior)).powi(2);
+    let fresnel = r0 + (1. - r0) * (1. - cos).powi(5);
+
+    Some((T, fresnel))
+  }
+
   /// * `O` - origin
   /// * `D` - direction
   #[allow(non_snake_case)]
-  fn closest_intersection(
+  pub(crate) fn closest_intersection(
     &self,
     O: Vector3<f32>,
     D: Vector3<f32>,
     min_t: f32,
     max_t: f32,
-  ) -> Option<(&Sphere, f32)> {
-    let mut closest_t = f32::INFINITY;
-    let mut closest_sphere: Option<&Sphere> = None;
+  ) -> Option<Hit<'_>> {
+    self.closest_shape_hit(O, D, min_t, max_t).map(|(_, hit)| hit)
+  }
 
-    for sphere in self.spheres {
-      let (t1, t2) = intersect_ray_sphere(O, D, sphere);
+  /// Like [`Scene::closest_intersection`], also telling whether the shape
+  /// hit is sampled as a light by [`Scene::direct_lighting`].
+  ///
+  /// * `O` - origin
+  /// * `D` - direction
+  #[allow(non_snake_case)]
+  pub(crate) fn closest_intersection_emitter(
+    &self,
+    O: Vector3<f32>,
+    D: Vector3<f32>,
+    min_t: f32,
+    max_t: f32,
+  ) -> Option<(Hit<'_>, bool)> {
+    let (i, hit) = self.closest_shape_hit(O, D, min_t, max_t)?;
+    let sampled = self.acceleration().emitters.binary_search(&i).is_ok();
+    Some((hit, sampled))
+  }
 
-      if t1 < closest_t && min_t < t1 && t1 < max_t {
-        closest_t = t1;
-        closest_sphere = Some(sphere);
-      }
+  /// Closest hit and the index of the shape it is on.
+  ///
+  /// * `O` - origin
+  /// * `D` - direction
+  #[allow(non_snake_case)]
+  fn closest_shape_hit(
+    &self,
+    O: Vector3<f32>,
+    D: Vector3<f32>,
+    min_t: f32,
+    max_t: f32,
+  ) -> Option<(usize, Hit<'_>)> {
+    let acceleration = self.acceleration();
+
+    let mut closest =
+      acceleration.bvh.closest(O, D, min_t, max_t, |i, max_t| {
+        let i = acceleration.bounded[i];
+        let hit = self.shapes[i].1.intersect(O, D, min_t, max_t)?;
+        Some((hit.t, (i, hit)))
+      });
 
-      if t2 < closest_t && min_t < t2 && t2 < max_t {
-        closest_t = t2;
-        closest_sphere = Some(sphere);
+    for &i in &acceleration.unbounded {
+      let max_t = closest.as_ref().map_or(max_t, |(_, hit)| hit.t);
+
+      if let Some(hit) = self.shapes[i].1.intersect(O, D, min_t, max_t) {
+        closest = Some((i, hit));
       }
     }
 
-    closest_sphere.map(|sphere| (sphere, closest_t))
+    closest
+  }
+
+  /// Share of light getting through whatever lies between `min_t` and
+  /// `max_t` along the ray: 1 when nothing does, 0 past an opaque shape.
+  ///
+  /// * `O` - origin
+  /// * `D` - direction
+  #[allow(non_snake_case)]
+  fn shadow_transmittance(
+    &self,
+    O: Vector3<f32>,
+    D: Vector3<f32>,
+    min_t: f32,
+    max_t: f32,
+  ) -> f32 {
+    let acceleration = self.acceleration();
+    let mut transmittance = 1.;
+
+    // Returns true once the light is fully blocked, to stop the search
+    let mut blocks = |i: usize| {
+      if let Some(hit) = self.shapes[i].1.intersect(O, D, min_t, max_t) {
+        transmittance *= hit.material.transparency.unwrap_or(0.);
+      }
+      transmittance <= 0.
+    };
+
+    let blocked = acceleration.unbounded.iter().any(|&i| blocks(i))
+      || acceleration
+        .bvh
+        .any(O, D, min_t, max_t, |i| blocks(acceleration.bounded[i]));
+
+    if blocked {
+      0.
+    } else {
+      transmittance
+    }
   }
 
   /// * `O` - origin
@@ -142,68 +693,219 @@ impl<'a> Scene<'a> {
     recursion_depth: Option<i32>,
   ) -> Color {
     let intersection = self.closest_intersection(O, D, min_t, max_t);
-    if let Some((sphere, closest_t)) = intersection {
-      if self.lights.is_empty() {
-        return sphere.color;
+    if let Some(hit) = intersection {
+      let material = hit.material;
+      let color = material.color_at(hit.uv, hit.point);
+
+      if self.lights.is_empty()
+        && self.acceleration().emitters.is_empty()
+        && !self.environment_lighting
+      {
+        return color + material.emission;
       }
 
-      let P = O + D * closest_t;
-      let N = (P - sphere.center).normalize();
+      let P = hit.point;
+      let N = hit.shading_normal();
 
-      let mut local_color =
-        Vector3::new(sphere.color[0], sphere.color[1], sphere.color[2]);
-
-      let intensity = self.compute_lighting(P, N, -D, sphere.specular);
-      local_color = local_color * intensity;
+      let reflectance = Reflectance::new(material, color);
+      let mut local_color = self.compute_lighting(P, N, -D, reflectance);
 
       let recursion_depth = recursion_depth.unwrap_or(0);
-      let r = sphere.reflective.unwrap_or(0.);
+      let transparency = material.transparency.unwrap_or(0.);
+
+      // Phong mirrors blend in a share of the reflected color, microfacet
+      // surfaces reflect their surroundings the more sharply the smoother
+      // they are
+      let (r, gloss) = match reflectance {
+        Reflectance::Phong { .. } => (material.reflective.unwrap_or(0.), 0.),
+        Reflectance::Microfacet { pbr, .. } => {
+          (0., (1. - pbr.roughness.clamp(0., 1.)).powi(2))
+        }
+      };
 
-      if recursion_depth > 0 && r > 0. {
-        let R = self.reflect_ray(-D, N);
+      let reflected_color = (recursion_depth > 0
+        && (r > 0. || gloss > 0. || transparency > 0.))
+        .then(|| {
+          let R = self.reflect_ray(-D, N);
+          self.trace_ray(P, R, 0.1, f32::INFINITY, Some(recursion_depth - 1))
+        });
+
+      if let Reflectance::Microfacet { base, pbr } = reflectance {
+        let f0 = microfacet::base_reflectance(base, pbr.metallic);
+        let fresnel =
+          microfacet::fresnel_rough(N.dot(-D.normalize()), f0, pbr.roughness);
+
+        // Rough surfaces blur their surroundings into the ambient light
+        let ambient = self.ambient_light(self.reflect_ray(-D, N));
+        let surroundings = reflected_color
+          .map_or(ambient, |reflected| ambient.lerp(reflected, gloss));
+        local_color += fresnel * surroundings;
+      }
 
-        let reflected_color =
-          self.trace_ray(P, R, 0.1, f32::INFINITY, Some(recursion_depth - 1));
+      if let Some(reflected_color) = reflected_color {
+        local_color = local_color.lerp(reflected_color, r);
+
+        if transparency > 0. {
+          let transmitted_color = match self.refract_ray(
+            D,
+            N,
+            hit.front_face,
+            material.refractive_index,
+          ) {
+            Some((T, fresnel)) => {
+              let refracted_color = self.trace_ray(
+                P,
+                T,
+                0.001,
+                f32::INFINITY,
+                Some(recursion_depth - 1),
+              );
+
+              refracted_color.lerp(reflected_color, fresnel)
+            }
+            // Total internal reflection
+            None => reflected_color,
+          };
+
+          local_color = local_color.lerp(transmitted_color, transparency);
+        }
+      }
 
-        let reflected_color = Vector3::new(
-          reflected_color[0],
-          reflected_color[1],
-          reflected_color[2],
-        );
+      return local_color + material.emission;
+    }
 
-        local_color = local_color * (1. - r) + reflected_color * r;
-      }
+    self.background(D)
+  }
+}
+
+/// How a shaded point reflects the light reaching it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Reflectance {
+  /// Lambertian diffuse filtered by `color`, plus a Phong highlight.
+  Phong { color: Color, specular: Option<f32> },
+  /// Cook–Torrance microfacet model.
+  Microfacet { base: Color, pbr: Pbr },
+}
 
-      return [local_color.x, local_color.y, local_color.z, 255.];
+impl Reflectance {
+  /// * `color` - surface color at the shaded point
+  pub(crate) fn new(material: &Material, color: Color) -> Self {
+    match material.pbr {
+      Some(pbr) => Self::Microfacet { base: color, pbr },
+      None => Self::Phong {
+        color,
+        specular: material.specular,
+      },
     }
+  }
 
-    self.background_color
+  /// Filter of the light reflected diffusely, e.g. ambient light.
+  ///
+  /// * `N` - normal
+  /// * `V` - view
+  #[allow(non_snake_case)]
+  pub(crate) fn diffuse(&self, N: Vector3<f32>, V: Vector3<f32>) -> Color {
+    match *self {
+      Self::Phong { color, .. } => color,
+      Self::Microfacet { base, pbr } => {
+        let f0 = microfacet::base_reflectance(base, pbr.metallic);
+        let fresnel =
+          microfacet::fresnel_rough(N.dot(V.normalize()), f0, pbr.roughness);
+        (Color::WHITE - fresnel) * base * (1. - pbr.metallic)
+      }
+    }
   }
 }
 
-/// * `O` - origin
-/// * `D` - direction
-#[allow(non_snake_case)]
-fn intersect_ray_sphere(
-  O: Vector3<f32>,
-  D: Vector3<f32>,
-  sphere: &Sphere,
-) -> (f32, f32) {
-  let r = sphere.radius;
-  let CO = O - sphere.center;
+/// 0 up to `edge0`, 1 from `edge1`, with a smooth Hermite curve in between.
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+  if edge0 >= edge1 {
+    return if x >= edge1 { 1. } else { 0. };
+  }
 
-  let k1 = D.dot(D);
-  let k2 = 2. * CO.dot(D);
-  let k3 = CO.dot(CO) - r * r;
+  let t = ((x - edge0) / (edge1 - edge0)).clamp(0., 1.);
+  t * t * (3. - 2. * t)
+}
 
-  let discriminant = k2 * k2 - 4. * k1 * k3;
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::primitive::{Mesh, Plane};
+
+  /// Unit square in the `z = 2` plane, split along its diagonal.
+  fn square() -> Mesh {
+    Mesh::new(
+      vec![
+        Vector3::new(-1., -1., 2.),
+        Vector3::new(1., -1., 2.),
+        Vector3::new(1., 1., 2.),
+        Vector3::new(-1., 1., 2.),
+      ],
+      vec![[0, 1, 2], [0, 2, 3]],
+      Material::new(Color::WHITE),
+    )
+  }
 
-  if discriminant < 0. {
-    return (f32::INFINITY, f32::INFINITY);
+  /// Distances along a grid of rays from the origin, `None` for misses.
+  fn hits(scene: &Scene) -> Vec<Option<f32>> {
+    (-4..=4)
+      .flat_map(|i| (-4..=4).map(move |j| (i, j)))
+      .map(|(i, j)| {
+        let direction = Vector3::new(i as f32 / 4., j as f32 / 4., 1.);
+        scene
+          .closest_intersection(
+            Vector3::new(0., 0., 0.),
+            direction,
+            1.,
+            f32::INFINITY,
+          )
+          .map(|hit| hit.t)
+      })
+      .collect()
   }
 
-  let t1 = (-k2 + discriminant.sqrt()) / (2. * k1);
-  let t2 = (-k2 - discriminant.sqrt()) / (2. * k1);
+  #[test]
+  fn shape_mut_rebuilds_mesh_bvh() {
+    let mut scene = Scene::new(Color::BLACK);
+    let handle = scene.add_shape(square());
+
+    // Builds the BVH of the mesh
+    assert!(hits(&scene).iter().any(Option::is_some));
 
-  (t1, t2)
+    let mesh = scene.shape_mut::<Mesh>(handle).unwrap();
+    for position in &mut mesh.positions {
+      position.z = 3.;
+    }
+    mesh.indices.truncate(1);
+
+    let mut edited = square();
+    for position in &mut edited.positions {
+      position.z = 3.;
+    }
+    edited.indices.truncate(1);
+    let mut expected = Scene::new(Color::BLACK).without_bvh();
+    expected.add_shape(edited);
+
+    assert_eq!(hits(&scene), hits(&expected));
+  }
+
+  #[test]
+  fn unlit_scenes_show_emission() {
+    let glow = Color::new(2., 1., 0.5);
+    let wall = Plane {
+      point: Vector3::new(0., 0., 2.),
+      normal: Vector3::new(0., 0., -1.),
+      material: Material::new(Color::BLACK).with_emission(glow),
+    };
+    let scene = Scene::new(Color::BLACK).with_shapes(&[wall]);
+
+    let color = scene.trace_ray(
+      Vector3::new(0., 0., 0.),
+      Vector3::new(0., 0., 1.),
+      1.,
+      f32::INFINITY,
+      None,
+    );
+    assert_eq!(color, glow);
+  }
 }
@@ -0,0 +1,249 @@
+//! Tiled rendering of a whole [`Canvas`] across several threads.
+
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::canvas::Canvas;
+use crate::primitive::Color;
+use crate::random::Rng;
+use crate::sampling::{Filter, Sampler};
+
+const DEFAULT_TILE_SIZE: u32 = 32;
+
+/// Below this the filter weights of a pixel are too small to divide by.
+const MIN_TOTAL_WEIGHT: f32 = 1e-3;
+
+/// Splits the canvas into square tiles that worker threads pick up one at a
+/// time, so the output doesn't depend on the thread count or scheduling.
+///
+/// Each pixel is shaded once per sample of the [`Sampler`], spread over the
+/// footprint of the [`Filter`] and averaged with its weights. Random patterns
+/// are seeded per pixel from `seed`, so renders are reproducible.
+#[derive(Debug, Clone)]
+pub struct Renderer {
+  threads: usize,
+  tile_size: u32,
+  sampler: Sampler,
+  filter: Filter,
+  seed: u64,
+}
+
+impl Default for Renderer {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Renderer {
+  /// Uses one thread per available core.
+  pub fn new() -> Self {
+    Self {
+      threads: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+      tile_size: DEFAULT_TILE_SIZE,
+      sampler: Sampler::default(),
+      filter: Filter::default(),
+      seed: 0,
+    }
+  }
+
+  pub fn with_threads(mut self, threads: usize) -> Self {
+    self.threads = threads.max(1);
+    self
+  }
+
+  pub fn with_tile_size(mut self, tile_size: u32) -> Self {
+    self.tile_size = tile_size.max(1);
+    self
+  }
+
+  pub fn with_sampler(mut self, sampler: Sampler) -> Self {
+    self.sampler = sampler.at_least_one();
+    self
+  }
+
+  pub fn with_filter(mut self, filter: Filter) -> Self {
+    self.filter = filter;
+    self
+  }
+
+  pub fn with_seed(mut self, seed: u64) -> Self {
+    self.seed = seed;
+    self
+  }
+
+  /// Calls `shade` with canvas coordinates around every pixel, the same ones
+  /// `Canvas::put_pixel` takes, and stores the filtered colors.
+  pub fn render<F>(&self, canvas: &mut Canvas, shade: F)
+  where
+    F: Fn(&Canvas, f32, f32) -> Color + Sync,
+  {
+    let tiles = Tile::split(canvas.width(), canvas.height(), self.tile_size);
+    let next_tile = AtomicUsize::new(0);
+
+    let rendered: Vec<(Tile, Vec<Color>)> = thread::scope(|scope| {
+      let canvas: &Canvas = canvas;
+      let workers: Vec<_> = (0..self.threads.min(tiles.len()))
+        .map(|_| {
+          scope.spawn(|| {
+            let mut done = Vec::new();
+
+            loop {
+              let i = next_tile.fetch_add(1, Ordering::Relaxed);
+              let Some(&tile) = tiles.get(i) else {
+                break;
+              };
+
+              let colors = tile
+                .pixels(canvas)
+                .map(|(x, y)| self.shade_pixel(canvas, &shade, x, y))
+                .collect();
+              done.push((tile, colors));
+            }
+
+            done
+          })
+        })
+        .collect();
+
+      workers
+        .into_iter()
+        .flat_map(|worker| worker.join().expect("render thread panicked"))
+        .collect()
+    });
+
+    for (tile, colors) in rendered {
+      for ((x, y), color) in tile.pixels(canvas).zip(colors) {
+        canvas.put_pixel(x, y, color);
+      }
+    }
+  }
+
+  fn shade_pixel<F>(&self, canvas: &Canvas, shade: &F, x: f32, y: f32) -> Color
+  where
+    F: Fn(&Canvas, f32, f32) -> Color,
+  {
+    let stream = ((x as i32 as u32 as u64) << 32) | y as i32 as u32 as u64;
+    let mut rng = Rng::with_stream(self.seed, stream);
+
+    let radius = self.filter.radius();
+    let mut color = Color::BLACK;
+    let mut total_weight = 0.;
+    // Plain average, in case negative lobes cancel the weights out
+    let mut unweighted = Color::BLACK;
+    let mut count = 0;
+
+    for (u, v) in self.sampler.samples(&mut rng) {
+      let dx = (2. * u - 1.) * radius;
+      let dy = (2. * v - 1.) * radius;
+
+      let weight = self.filter.weight(dx, dy);
+      if weight == 0. {
+        continue;
+      }
+
+      let sample = shade(canvas, x + dx, y + dy);
+      color += sample * weight;
+      total_weight += weight;
+      unweighted += sample;
+      count += 1;
+    }
+
+    if total_weight > MIN_TOTAL_WEIGHT {
+      color / total_weight
+    } else if count > 0 {
+      unweighted / count as f32
+    } else {
+      Color::BLACK
+    }
+  }
+}
+
+/// Renders `canvas` on all cores with the default [`Renderer`].
+pub fn render<F>(canvas: &mut Canvas, shade: F)
+where
+  F: Fn(&Canvas, f32, f32) -> Color + Sync,
+{
+  Renderer::new().render(canvas, shade);
+}
+
+/// Rectangle of pixels, in rows and columns from the top left corner.
+#[derive(Debug, Clone, Copy)]
+struct Tile {
+  column: u32,
+  row: u32,
+  width: u32,
+  height: u32,
+}
+
+impl Tile {
+  fn split(width: u32, height: u32, size: u32) -> Vec<Self> {
+    let mut tiles = Vec::new();
+
+    for row in (0..height).step_by(size as usize) {
+      for column in (0..width).step_by(size as usize) {
+        tiles.push(Self {
+          column,
+          row,
+          width: size.min(width - column),
+          height: size.min(height - row),
+        });
+      }
+    }
+
+    tiles
+  }
+
+  /// Canvas coordinates of the pixels, with the origin at the center.
+  fn pixels(&self, canvas: &Canvas) -> impl Iterator<Item = (f32, f32)> {
+    let half_width = (canvas.width() / 2) as i32;
+    let half_height = (canvas.height() / 2) as i32;
+    let Tile {
+      column,
+      row,
+      width,
+      height,
+    } = *self;
+
+    (row..row + height).flat_map(move |row| {
+      (column..column + width).map(move |column| {
+        let x = column as i32 - half_width;
+        let y = half_height - row as i32 - 1;
+        (x as f32, y as f32)
+      })
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+  use std::sync::Mutex;
+
+  use super::*;
+
+  #[test]
+  fn renders_every_pixel_of_odd_canvases_once() {
+    let (width, height) = (7, 5);
+    let mut canvas = Canvas::new(width, height, 1., 1.);
+    let shaded = Mutex::new(HashMap::new());
+
+    Renderer::new().with_tile_size(3).render(&mut canvas, |_, x, y| {
+      *shaded.lock().unwrap().entry((x as i32, y as i32)).or_insert(0) += 1;
+      Color::new(x, y, 1.)
+    });
+
+    let shaded = shaded.into_inner().unwrap();
+    assert_eq!(shaded.len(), (width * height) as usize);
+    assert!(shaded.values().all(|&count| count == 1));
+
+    // Each pixel holds the coordinates it was shaded at
+    for (index, color) in canvas.hdr_data().iter().enumerate() {
+      let column = index as i32 % width as i32;
+      let row = index as i32 / width as i32;
+      let x = column - width as i32 / 2;
+      let y = height as i32 / 2 - row - 1;
+      assert_eq!(*color, Color::new(x as f32, y as f32, 1.));
+    }
+  }
+}
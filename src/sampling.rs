@@ -0,0 +1,195 @@
+//! Sample patterns and reconstruction filters for anti-aliasing.
+
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+use cgmath::Vector3;
+
+use crate::random::Rng;
+
+/// Where the rays of a pixel go, as offsets in `[0, 1)²` over the filter
+/// footprint.
+#[derive(Debug, Clone, Copy)]
+pub enum Sampler {
+  /// `n×n` regular grid. `Grid(1)` traces one ray through the pixel itself.
+  Grid(u32),
+  /// `n×n` grid rotated so that no two samples share a row or column.
+  RotatedGrid(u32),
+  /// `n×n` grid, jittered inside each cell.
+  Stratified(u32),
+  /// `n` uniformly random samples.
+  Random(u32),
+}
+
+impl Default for Sampler {
+  fn default() -> Self {
+    Self::Grid(1)
+  }
+}
+
+impl Sampler {
+  /// The same pattern with at least one sample, since a count of 0 would
+  /// leave pixels black.
+  pub fn at_least_one(self) -> Self {
+    match self {
+      Sampler::Grid(n) => Sampler::Grid(n.max(1)),
+      Sampler::RotatedGrid(n) => Sampler::RotatedGrid(n.max(1)),
+      Sampler::Stratified(n) => Sampler::Stratified(n.max(1)),
+      Sampler::Random(n) => Sampler::Random(n.max(1)),
+    }
+  }
+
+  pub fn samples(&self, rng: &mut Rng) -> Vec<(f32, f32)> {
+    match self.at_least_one() {
+      Sampler::Grid(n) => grid(n)
+        .map(|(i, j)| ((i + 0.5) / n as f32, (j + 0.5) / n as f32))
+        .collect(),
+      Sampler::RotatedGrid(n) => {
+        let n2 = (n * n) as f32;
+        grid(n)
+          .map(|(i, j)| {
+            let x = (i * n as f32 + j + 0.5) / n2;
+            let y = (j * n as f32 + (n as f32 - 1. - i) + 0.5) / n2;
+            (x, y)
+          })
+          .collect()
+      }
+      Sampler::Stratified(n) => grid(n)
+        .map(|(i, j)| {
+          let x = (i + rng.next_f32()) / n as f32;
+          let y = (j + rng.next_f32()) / n as f32;
+          (x, y)
+        })
+        .collect(),
+      Sampler::Random(n) => {
+        (0..n).map(|_| (rng.next_f32(), rng.next_f32())).collect()
+      }
+    }
+  }
+}
+
+/// Shirley's concentric mapping of `[0, 1)²` onto the unit disk, which keeps
+/// stratified samples evenly spread.
+pub fn concentric_disk(u: f32, v: f32) -> (f32, f32) {
+  let a = 2. * u - 1.;
+  let b = 2. * v - 1.;
+
+  if a == 0. && b == 0. {
+    return (0., 0.);
+  }
+
+  let (r, theta) = if a.abs() > b.abs() {
+    (a, FRAC_PI_4 * (b / a))
+  } else {
+    (b, FRAC_PI_2 - FRAC_PI_4 * (a / b))
+  };
+
+  (r * theta.cos(), r * theta.sin())
+}
+
+/// Maps `[0, 1)²` onto the hemisphere around `z`, with a density
+/// proportional to the cosine of the angle to `z`, i.e. `cos θ / π`.
+pub fn cosine_hemisphere(u: f32, v: f32) -> Vector3<f32> {
+  let (x, y) = concentric_disk(u, v);
+  let z = (1. - x * x - y * y).max(0.).sqrt();
+  Vector3::new(x, y, z)
+}
+
+/// Maps `[0, 1)²` uniformly onto the unit sphere.
+pub fn uniform_sphere(u: f32, v: f32) -> Vector3<f32> {
+  let z = 1. - 2. * u;
+  let r = (1. - z * z).max(0.).sqrt();
+  let phi = 2. * PI * v;
+  Vector3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+fn grid(n: u32) -> impl Iterator<Item = (f32, f32)> {
+  (0..n).flat_map(move |j| (0..n).map(move |i| (i as f32, j as f32)))
+}
+
+/// Weights samples by their distance to the pixel, in pixels.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Filter {
+  /// Equal weights over the pixel.
+  #[default]
+  Box,
+  Tent {
+    radius: f32,
+  },
+  Gaussian {
+    radius: f32,
+    alpha: f32,
+  },
+  /// Mitchell–Netravali cubic, sharper than the others thanks to its
+  /// negative lobes. `b = c = 1/3` is the usual choice.
+  Mitchell {
+    radius: f32,
+    b: f32,
+    c: f32,
+  },
+}
+
+impl Filter {
+  pub fn tent() -> Self {
+    Self::Tent { radius: 1. }
+  }
+
+  pub fn gaussian() -> Self {
+    Self::Gaussian {
+      radius: 1.5,
+      alpha: 2.,
+    }
+  }
+
+  pub fn mitchell() -> Self {
+    Self::Mitchell {
+      radius: 2.,
+      b: 1. / 3.,
+      c: 1. / 3.,
+    }
+  }
+
+  /// Half width of the square the filter covers.
+  pub fn radius(&self) -> f32 {
+    match *self {
+      Filter::Box => 0.5,
+      Filter::Tent { radius }
+      | Filter::Gaussian { radius, .. }
+      | Filter::Mitchell { radius, .. } => radius,
+    }
+  }
+
+  pub fn weight(&self, dx: f32, dy: f32) -> f32 {
+    self.weight_1d(dx) * self.weight_1d(dy)
+  }
+
+  fn weight_1d(&self, x: f32) -> f32 {
+    let x = x.abs();
+
+    match *self {
+      Filter::Box => 1.,
+      Filter::Tent { radius } => (1. - x / radius).max(0.),
+      Filter::Gaussian { radius, alpha } => {
+        ((-alpha * x * x).exp() - (-alpha * radius * radius).exp()).max(0.)
+      }
+      Filter::Mitchell { radius, b, c } => {
+        // The cubic is defined over [-2, 2]
+        let x = 2. * x / radius;
+
+        if x < 1. {
+          ((12. - 9. * b - 6. * c) * x.powi(3)
+            + (-18. + 12. * b + 6. * c) * x.powi(2)
+            + (6. - 2. * b))
+            / 6.
+        } else if x < 2. {
+          ((-b - 6. * c) * x.powi(3)
+            + (6. * b + 30. * c) * x.powi(2)
+            + (-12. * b - 48. * c) * x
+            + (8. * b + 24. * c))
+            / 6.
+        } else {
+          0.
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,574 @@
+//! TOML scene description, so scenes can be tweaked without recompiling.
+//!
+//! ```toml
+//! background = [0, 0, 0, 255]
+//! shadow = true
+//! recursion_depth = 3
+//!
+//! [canvas]
+//! width = 1200
+//! height = 1200
+//!
+//! [camera]
+//! position = [3, 0, 1]
+//! target = [2, 0, 2]
+//!
+//! [[spheres]]
+//! center = [0, -1, 3]
+//! radius = 1
+//! color = [255, 0, 0, 255]
+//! specular = 500
+//! reflective = 0.2
+//!
+//! [[planes]]
+//! point = [0, -1, 0]
+//! normal = [0, 1, 0]
+//! color = "#ffff00"
+//!
+//! [[lights]]
+//! type = "point"
+//! position = [2, 1, 0]
+//! intensity = 0.6
+//! color = "#fff4e0"
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use cgmath::{Deg, Vector3};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::primitive::{
+  Attenuation, Color, Light, LightType, Material, Pbr, Plane, Sphere,
+};
+use crate::raytracer::Scene;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneFile {
+  #[serde(default)]
+  pub canvas: CanvasSettings,
+  #[serde(default)]
+  pub camera: CameraSettings,
+  #[serde(default = "default_background", deserialize_with = "color")]
+  pub background: Color,
+  #[serde(default)]
+  pub shadow: bool,
+  #[serde(default)]
+  pub recursion_depth: i32,
+  #[serde(default)]
+  pub spheres: Vec<SphereSettings>,
+  #[serde(default)]
+  pub planes: Vec<PlaneSettings>,
+  #[serde(default)]
+  pub lights: Vec<LightSettings>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CanvasSettings {
+  pub width: u32,
+  pub height: u32,
+  #[serde(default = "one")]
+  pub viewport_size: f32,
+  #[serde(default = "one")]
+  pub projection_plane_z: f32,
+}
+
+impl Default for CanvasSettings {
+  fn default() -> Self {
+    Self {
+      width: 1200,
+      height: 1200,
+      viewport_size: 1.,
+      projection_plane_z: 1.,
+    }
+  }
+}
+
+/// Without a `target` the camera looks down `+z`; without `fov` (vertical,
+/// in degrees) it sees the canvas viewport.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraSettings {
+  #[serde(default)]
+  pub position: [f32; 3],
+  pub target: Option<[f32; 3]>,
+  #[serde(default = "default_up")]
+  pub up: [f32; 3],
+  pub fov: Option<f32>,
+}
+
+impl Default for CameraSettings {
+  fn default() -> Self {
+    Self {
+      position: [0., 0., 0.],
+      target: None,
+      up: default_up(),
+      fov: None,
+    }
+  }
+}
+
+/// Flattened into the shapes, which reject the keys left over.
+#[derive(Debug, Deserialize)]
+pub struct MaterialSettings {
+  #[serde(deserialize_with = "color")]
+  pub color: Color,
+  pub specular: Option<f32>,
+  pub reflective: Option<f32>,
+  pub transparency: Option<f32>,
+  pub refractive_index: Option<f32>,
+  /// Setting either switches to the microfacet model.
+  pub metallic: Option<f32>,
+  pub roughness: Option<f32>,
+  /// Makes the surface glow with `color` times this intensity.
+  pub emission: Option<f32>,
+}
+
+impl MaterialSettings {
+  fn material(&self) -> Material {
+    Material {
+      color: self.color,
+      texture: None,
+      normal_map: None,
+      pbr: (self.metallic.is_some() || self.roughness.is_some()).then(|| {
+        Pbr::new(self.metallic.unwrap_or(0.), self.roughness.unwrap_or(0.5))
+      }),
+      specular: self.specular,
+      reflective: self.reflective,
+      transparency: self.transparency,
+      refractive_index: self.refractive_index.unwrap_or(1.),
+      emission: self.color * self.emission.unwrap_or(0.),
+    }
+  }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SphereSettings {
+  pub center: [f32; 3],
+  pub radius: f32,
+  #[serde(flatten)]
+  pub material: MaterialSettings,
+  // `deny_unknown_fields` has no effect through `flatten`
+  #[serde(flatten)]
+  unknown: HashMap<String, toml::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlaneSettings {
+  pub point: [f32; 3],
+  pub normal: [f32; 3],
+  #[serde(flatten)]
+  pub material: MaterialSettings,
+  #[serde(flatten)]
+  unknown: HashMap<String, toml::Value>,
+}
+
+/// `color` (sRGB, white by default) tints the `intensity`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum LightSettings {
+  Ambient {
+    intensity: f32,
+    #[serde(default = "white", deserialize_with = "color")]
+    color: Color,
+  },
+  Point {
+    position: [f32; 3],
+    intensity: f32,
+    #[serde(default = "white", deserialize_with = "color")]
+    color: Color,
+    #[serde(default)]
+    attenuation: AttenuationSettings,
+    range: Option<f32>,
+  },
+  Directional {
+    direction: [f32; 3],
+    intensity: f32,
+    #[serde(default = "white", deserialize_with = "color")]
+    color: Color,
+  },
+  /// Cone angles are in degrees, measured from `direction`.
+  Spot {
+    position: [f32; 3],
+    direction: [f32; 3],
+    inner_angle: f32,
+    outer_angle: f32,
+    intensity: f32,
+    #[serde(default = "white", deserialize_with = "color")]
+    color: Color,
+    #[serde(default)]
+    attenuation: AttenuationSettings,
+    range: Option<f32>,
+  },
+  /// Lit by `samples × samples` shadow rays.
+  Rect {
+    corner: [f32; 3],
+    edge_u: [f32; 3],
+    edge_v: [f32; 3],
+    #[serde(default = "default_light_samples")]
+    samples: u32,
+    intensity: f32,
+    #[serde(default = "white", deserialize_with = "color")]
+    color: Color,
+    #[serde(default)]
+    attenuation: AttenuationSettings,
+    range: Option<f32>,
+  },
+  /// Lit by `samples × samples` shadow rays.
+  Sphere {
+    center: [f32; 3],
+    radius: f32,
+    #[serde(default = "default_light_samples")]
+    samples: u32,
+    intensity: f32,
+    #[serde(default = "white", deserialize_with = "color")]
+    color: Color,
+    #[serde(default)]
+    attenuation: AttenuationSettings,
+    range: Option<f32>,
+  },
+}
+
+/// `"none"`, `"inverse_square"` or
+/// `{ polynomial = { constant = 1, linear = 0.1, quadratic = 0.01 } }`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum AttenuationSettings {
+  #[default]
+  None,
+  InverseSquare,
+  Polynomial {
+    #[serde(default)]
+    constant: f32,
+    #[serde(default)]
+    linear: f32,
+    #[serde(default)]
+    quadratic: f32,
+  },
+}
+
+impl From<&AttenuationSettings> for Attenuation {
+  fn from(settings: &AttenuationSettings) -> Self {
+    match *settings {
+      AttenuationSettings::None => Attenuation::None,
+      AttenuationSettings::InverseSquare => Attenuation::InverseSquare,
+      AttenuationSettings::Polynomial {
+        constant,
+        linear,
+        quadratic,
+      } => Attenuation::Polynomial {
+        constant,
+        linear,
+        quadratic,
+      },
+    }
+  }
+}
+
+fn one() -> f32 {
+  1.
+}
+
+fn default_light_samples() -> u32 {
+  4
+}
+
+fn white() -> Color {
+  Color::WHITE
+}
+
+fn default_background() -> Color {
+  Color::BLACK
+}
+
+/// Colors are sRGB, written as `"#rrggbb"` or as 8-bit `[r, g, b]`,
+/// optionally followed by an alpha channel which is ignored.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorSettings {
+  Hex(String),
+  Rgb([f32; 3]),
+  Rgba([f32; 4]),
+}
+
+fn color<'de, D: Deserializer<'de>>(
+  deserializer: D,
+) -> Result<Color, D::Error> {
+  let rgb8 =
+    |[r, g, b]: [f32; 3]| Color::from_srgb(r / 255., g / 255., b / 255.);
+
+  match ColorSettings::deserialize(deserializer)? {
+    ColorSettings::Hex(hex) => hex.parse().map_err(D::Error::custom),
+    ColorSettings::Rgb(rgb) => Ok(rgb8(rgb)),
+    ColorSettings::Rgba([r, g, b, _]) => Ok(rgb8([r, g, b])),
+  }
+}
+
+fn default_up() -> [f32; 3] {
+  [0., 1., 0.]
+}
+
+impl SceneFile {
+  pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)
+      .with_context(|| format!("failed to read {}", path.display()))?;
+
+    Self::parse(&source).with_context(|| format!("in {}", path.display()))
+  }
+
+  pub fn parse(source: &str) -> Result<Self> {
+    let file: Self = toml::from_str(source)?;
+
+    if file.canvas.width == 0 || file.canvas.height == 0 {
+      bail!("the canvas must be at least 1 pixel wide and high");
+    }
+
+    let unknown = file
+      .spheres
+      .iter()
+      .map(|sphere| ("spheres", &sphere.unknown))
+      .chain(file.planes.iter().map(|plane| ("planes", &plane.unknown)));
+
+    for (section, fields) in unknown {
+      if let Some(field) = fields.keys().min() {
+        bail!("unknown field `{field}` in [[{section}]]");
+      }
+    }
+
+    Ok(file)
+  }
+
+  pub fn canvas(&self) -> Canvas {
+    let canvas = &self.canvas;
+    Canvas::new(
+      canvas.width,
+      canvas.height,
+      canvas.viewport_size,
+      canvas.projection_plane_z,
+    )
+  }
+
+  pub fn camera(&self) -> Camera {
+    let settings = &self.camera;
+    let position = Vector3::from(settings.position);
+    let target =
+      settings.target.map_or(position + Vector3::unit_z(), Vector3::from);
+
+    let camera = Camera::look_at(position, target).with_up(settings.up.into());
+
+    match settings.fov {
+      Some(fov) => camera.with_fov(Deg(fov)),
+      None => {
+        let half_height = self.canvas.viewport_size / 2.;
+        let fov = 2. * (half_height / self.canvas.projection_plane_z).atan();
+        camera.with_fov(cgmath::Rad(fov))
+      }
+    }
+  }
+
+  /// Builds a [`Scene`] holding every sphere, plane and light of the file.
+  pub fn scene(&self) -> Scene {
+    let mut scene = Scene::new(self.background)
+      .with_shapes(&self.spheres())
+      .with_shapes(&self.planes())
+      .with_lights(&self.lights());
+    scene.shadow = self.shadow;
+    scene
+  }
+
+  pub fn spheres(&self) -> Vec<Sphere> {
+    self
+      .spheres
+      .iter()
+      .map(|sphere| Sphere {
+        center: sphere.center.into(),
+        radius: sphere.radius,
+        material: sphere.material.material(),
+      })
+      .collect()
+  }
+
+  pub fn planes(&self) -> Vec<Plane> {
+    self
+      .planes
+      .iter()
+      .map(|plane| Plane {
+        point: plane.point.into(),
+        normal: plane.normal.into(),
+        material: plane.material.material(),
+      })
+      .collect()
+  }
+
+  pub fn lights(&self) -> Vec<Light> {
+    self
+      .lights
+      .iter()
+      .map(|light| match light {
+        LightSettings::Ambient { intensity, color } => {
+          Light::colored(LightType::Ambient, *color * *intensity)
+        }
+        LightSettings::Point {
+          position,
+          intensity,
+          color,
+          attenuation,
+          range,
+        } => Light {
+          attenuation: attenuation.into(),
+          range: *range,
+          ..Light::colored(
+            LightType::Point((*position).into()),
+            *color * *intensity,
+          )
+        },
+        LightSettings::Directional {
+          direction,
+          intensity,
+          color,
+        } => Light::colored(
+          LightType::Directional((*direction).into()),
+          *color * *intensity,
+        ),
+        LightSettings::Spot {
+          position,
+          direction,
+          inner_angle,
+          outer_angle,
+          intensity,
+          color,
+          attenuation,
+          range,
+        } => Light {
+          attenuation: attenuation.into(),
+          range: *range,
+          ..Light::colored(
+            LightType::Spot {
+              position: (*position).into(),
+              direction: (*direction).into(),
+              inner_angle: Deg(*inner_angle).into(),
+              outer_angle: Deg(*outer_angle).into(),
+            },
+            *color * *intensity,
+          )
+        },
+        LightSettings::Rect {
+          corner,
+          edge_u,
+          edge_v,
+          samples,
+          intensity,
+          color,
+          attenuation,
+          range,
+        } => Light {
+          attenuation: attenuation.into(),
+          range: *range,
+          ..Light::colored(
+            LightType::RectArea {
+              corner: (*corner).into(),
+              edge_u: (*edge_u).into(),
+              edge_v: (*edge_v).into(),
+              samples: *samples,
+            },
+            *color * *intensity,
+          )
+        },
+        LightSettings::Sphere {
+          center,
+          radius,
+          samples,
+          intensity,
+          color,
+          attenuation,
+          range,
+        } => Light {
+          attenuation: attenuation.into(),
+          range: *range,
+          ..Light::colored(
+            LightType::SphereArea {
+              center: (*center).into(),
+              radius: *radius,
+              samples: *samples,
+            },
+            *color * *intensity,
+          )
+        },
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::render::Renderer;
+
+  const SPHERE: &str = r#"
+[[spheres]]
+center = [0, -1, 3]
+radius = 1
+color = [255, 0, 0]
+reflective = 0.2
+"#;
+
+  #[test]
+  fn parses_material_keys() {
+    let file = SceneFile::parse(SPHERE).unwrap();
+    assert_eq!(file.spheres[0].material.reflective, Some(0.2));
+  }
+
+  #[test]
+  fn rejects_misspelled_material_keys() {
+    let sphere = SPHERE.replace("reflective", "reflectiv");
+    let error = SceneFile::parse(&sphere).unwrap_err();
+    assert!(error.to_string().contains("reflectiv"), "{error}");
+
+    let plane = r##"
+[[planes]]
+point = [0, -1, 0]
+normal = [0, 1, 0]
+color = "#ffff00"
+specularr = 10
+"##;
+    assert!(SceneFile::parse(plane).is_err());
+  }
+
+  #[test]
+  fn renders_odd_canvas_sizes() {
+    let source = r#"
+background = [0, 0, 255]
+
+[canvas]
+width = 21
+height = 15
+"#;
+    let file = SceneFile::parse(source).unwrap();
+
+    let scene = file.scene();
+    let rays = file.camera().rays(&file.canvas());
+    let mut canvas = file.canvas();
+    Renderer::new().render(&mut canvas, |_, x, y| {
+      let (origin, direction) = rays.ray(x, y);
+      scene.trace_ray(origin, direction, 1., f32::INFINITY, None)
+    });
+
+    assert_eq!((canvas.width(), canvas.height()), (21, 15));
+    // Every pixel shows the background
+    assert!(canvas.hdr_data().iter().all(|&color| color == file.background));
+  }
+
+  #[test]
+  fn rejects_empty_canvases() {
+    let source = "[canvas]\nwidth = 0\nheight = 10\n";
+    assert!(SceneFile::parse(source).is_err());
+  }
+}
@@ -0,0 +1,600 @@
+use std::any::Any;
+use std::f32::consts::PI;
+
+use cgmath::{InnerSpace, Vector2, Vector3};
+
+use crate::bvh::{Aabb, Bvh};
+use crate::primitive::{
+  Color, Direction, Disk, Material, Mesh, Plane, Position, Sphere, Triangle,
+};
+use crate::sampling::concentric_disk;
+use crate::texture::{planar_uv, spherical_uv};
+
+/// A ray hit on the surface of a shape.
+///
+/// `normal` always faces the incoming ray; `front_face` tells whether the ray
+/// hit the outside of the surface, i.e. the side its own normal points to.
+/// `uv` are the texture coordinates of the hit, and `tangent` and
+/// `bitangent` the unit directions along the surface in which `u` and `v`
+/// grow, which may not be exactly perpendicular to `normal`.
+#[derive(Debug)]
+pub struct Hit<'a> {
+  pub t: f32,
+  pub point: Position,
+  pub normal: Direction,
+  pub front_face: bool,
+  pub uv: Vector2<f32>,
+  pub tangent: Direction,
+  pub bitangent: Direction,
+  pub material: &'a Material,
+}
+
+impl Hit<'_> {
+  /// The normal to shade with: `normal`, bent by the normal map of the
+  /// material if it has one.
+  pub fn shading_normal(&self) -> Direction {
+    match &self.material.normal_map {
+      Some(normal_map) => normal_map.normal(self),
+      None => self.normal,
+    }
+  }
+}
+
+/// Anything a ray can be intersected with.
+pub trait Shape: Any + Send + Sync {
+  /// Returns the closest hit with `min_t < t < max_t`, if any.
+  ///
+  /// * `O` - origin
+  /// * `D` - direction
+  #[allow(non_snake_case)]
+  fn intersect(
+    &self,
+    O: Vector3<f32>,
+    D: Vector3<f32>,
+    min_t: f32,
+    max_t: f32,
+  ) -> Option<Hit<'_>>;
+
+  /// Bounds used to build the scene's acceleration structure, or `None` for
+  /// unbounded shapes, which are tested against every ray.
+  fn bounding_box(&self) -> Option<Aabb> {
+    None
+  }
+
+  /// Drops whatever the shape caches about its geometry, before it is
+  /// edited.
+  fn invalidate(&mut self) {}
+
+  /// Light the surface emits, which makes the scene sample it as a light
+  /// through [`Shape::sample_towards`]. Shapes that cannot be sampled keep
+  /// the default black, their material still glowing where it is seen.
+  fn emission(&self) -> Color {
+    Color::BLACK
+  }
+
+  /// Picks a point of the surface for `(u, v)` in `[0, 1)²` to light `P`
+  /// from, along with the solid angle the whole surface covers from `P` as
+  /// estimated with that point. `None` when the shape cannot be sampled or
+  /// `P` does not see the point.
+  #[allow(non_snake_case)]
+  fn sample_towards(
+    &self,
+    P: Position,
+    u: f32,
+    v: f32,
+  ) -> Option<(Position, f32)> {
+    let _ = (P, u, v);
+    None
+  }
+}
+
+impl Shape for Sphere {
+  #[allow(non_snake_case)]
+  fn intersect(
+    &self,
+    O: Vector3<f32>,
+    D: Vector3<f32>,
+    min_t: f32,
+    max_t: f32,
+  ) -> Option<Hit<'_>> {
+    let (t1, t2) = intersect_ray_sphere(O, D, self);
+
+    // t2 <= t1, so the first root in range is the closest one
+    let t = [t2, t1].into_iter().find(|&t| min_t < t && t < max_t)?;
+
+    let P = O + D * t;
+    let N = (P - self.center).normalize();
+    let front_face = N.dot(D) < 0.;
+
+    // Around the y axis like `u`, falling back to any frame at the poles
+    let (tangent, bitangent) = if N.x.abs() + N.z.abs() > f32::EPSILON {
+      let T = Vector3::new(-N.z, 0., N.x).normalize();
+      (T, T.cross(N))
+    } else {
+      orthonormal_basis(N)
+    };
+
+    Some(Hit {
+      t,
+      point: P,
+      normal: if front_face { N } else { -N },
+      front_face,
+      uv: spherical_uv(N),
+      tangent,
+      bitangent,
+      material: &self.material,
+    })
+  }
+
+  fn bounding_box(&self) -> Option<Aabb> {
+    let r = Vector3::new(self.radius, self.radius, self.radius);
+    Some(Aabb::new(self.center - r, self.center + r))
+  }
+
+  fn emission(&self) -> Color {
+    self.material.emission
+  }
+
+  #[allow(non_snake_case)]
+  fn sample_towards(
+    &self,
+    P: Position,
+    u: f32,
+    v: f32,
+  ) -> Option<(Position, f32)> {
+    sample_sphere_cap(self.center, self.radius, P, u, v)
+  }
+}
+
+impl Shape for Plane {
+  #[allow(non_snake_case)]
+  fn intersect(
+    &self,
+    O: Vector3<f32>,
+    D: Vector3<f32>,
+    min_t: f32,
+    max_t: f32,
+  ) -> Option<Hit<'_>> {
+    let (t, N, front_face) =
+      intersect_ray_plane(O, D, self.point, self.normal)?;
+
+    if t <= min_t || max_t <= t {
+      return None;
+    }
+
+    let P = O + D * t;
+    let (tangent, bitangent) = orthonormal_basis(self.normal.normalize());
+
+    Some(Hit {
+      t,
+      point: P,
+      normal: N,
+      front_face,
+      uv: planar_uv(P - self.point, self.normal),
+      tangent,
+      bitangent,
+      material: &self.material,
+    })
+  }
+}
+
+impl Shape for Disk {
+  #[allow(non_snake_case)]
+  fn intersect(
+    &self,
+    O: Vector3<f32>,
+    D: Vector3<f32>,
+    min_t: f32,
+    max_t: f32,
+  ) -> Option<Hit<'_>> {
+    let (t, N, front_face) =
+      intersect_ray_plane(O, D, self.center, self.normal)?;
+
+    if t <= min_t || max_t <= t {
+      return None;
+    }
+
+    let P = O + D * t;
+    let CP = P - self.center;
+
+    if CP.dot(CP) > self.radius * self.radius {
+      return None;
+    }
+
+    // The disk spans [0, 1]²
+    let uv = planar_uv(CP, self.normal) / (2. * self.radius);
+    let (tangent, bitangent) = orthonormal_basis(self.normal.normalize());
+
+    Some(Hit {
+      t,
+      point: P,
+      normal: N,
+      front_face,
+      uv: uv + Vector2::new(0.5, 0.5),
+      tangent,
+      bitangent,
+      material: &self.material,
+    })
+  }
+
+  fn bounding_box(&self) -> Option<Aabb> {
+    let n = self.normal.normalize();
+    let extent = Vector3::new(
+      (1. - n.x * n.x).max(0.).sqrt(),
+      (1. - n.y * n.y).max(0.).sqrt(),
+      (1. - n.z * n.z).max(0.).sqrt(),
+    ) * self.radius;
+
+    Some(Aabb::new(self.center - extent, self.center + extent))
+  }
+
+  fn emission(&self) -> Color {
+    self.material.emission
+  }
+
+  #[allow(non_snake_case)]
+  fn sample_towards(
+    &self,
+    P: Position,
+    u: f32,
+    v: f32,
+  ) -> Option<(Position, f32)> {
+    let normal = self.normal.normalize();
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    let (x, y) = concentric_disk(u, v);
+    let point = self.center + (tangent * x + bitangent * y) * self.radius;
+
+    let area = PI * self.radius * self.radius;
+    Some((point, solid_angle(P, point, normal, area)?))
+  }
+}
+
+impl Shape for Triangle {
+  #[allow(non_snake_case)]
+  fn intersect(
+    &self,
+    O: Vector3<f32>,
+    D: Vector3<f32>,
+    min_t: f32,
+    max_t: f32,
+  ) -> Option<Hit<'_>> {
+    let [A, B, C] = self.vertices;
+    let (t, u, v) = intersect_ray_triangle(O, D, A, B, C)?;
+
+    if t <= min_t || max_t <= t {
+      return None;
+    }
+
+    let N = (B - A).cross(C - A).normalize();
+    let front_face = N.dot(D) < 0.;
+
+    Some(Hit {
+      t,
+      point: O + D * t,
+      normal: if front_face { N } else { -N },
+      front_face,
+      uv: Vector2::new(u, v),
+      tangent: (B - A).normalize(),
+      bitangent: (C - A).normalize(),
+      material: &self.material,
+    })
+  }
+
+  fn bounding_box(&self) -> Option<Aabb> {
+    Some(Aabb::from_points(self.vertices))
+  }
+
+  fn emission(&self) -> Color {
+    self.material.emission
+  }
+
+  #[allow(non_snake_case)]
+  fn sample_towards(
+    &self,
+    P: Position,
+    u: f32,
+    v: f32,
+  ) -> Option<(Position, f32)> {
+    sample_triangle(self.vertices, P, u, v)
+  }
+}
+
+impl Shape for Mesh {
+  #[allow(non_snake_case)]
+  fn intersect(
+    &self,
+    O: Vector3<f32>,
+    D: Vector3<f32>,
+    min_t: f32,
+    max_t: f32,
+  ) -> Option<Hit<'_>> {
+    let bvh = self.bvh.get_or_init(|| {
+      let boxes: Vec<_> = (0..self.indices.len())
+        .map(|face| Aabb::from_points(self.triangle(face)))
+        .collect();
+      Bvh::build(&boxes)
+    });
+
+    let (face, t, u, v) = bvh.closest(O, D, min_t, max_t, |face, max_t| {
+      let [A, B, C] = self.triangle(face);
+      let (t, u, v) = intersect_ray_triangle(O, D, A, B, C)?;
+
+      (min_t < t && t < max_t).then_some((t, (face, t, u, v)))
+    })?;
+    let [A, B, C] = self.triangle(face);
+
+    let geometric_normal = (B - A).cross(C - A);
+    let N = match &self.normals {
+      Some(normals) => {
+        let [a, b, c] = self.indices[face].map(|i| normals[i as usize]);
+        ((1. - u - v) * a + u * b + v * c).normalize()
+      }
+      None => geometric_normal.normalize(),
+    };
+    let front_face = geometric_normal.dot(D) < 0.;
+
+    // Barycentric coordinates stand in for missing texture coordinates
+    let (uv, tangent, bitangent) = match &self.uvs {
+      Some(uvs) => {
+        let [a, b, c] = self.indices[face].map(|i| uvs[i as usize]);
+        let (tangent, bitangent) = uv_derivatives(B - A, C - A, b - a, c - a)
+          .unwrap_or_else(|| orthonormal_basis(N));
+        ((1. - u - v) * a + u * b + v * c, tangent, bitangent)
+      }
+      None => (Vector2::new(u, v), (B - A).normalize(), (C - A).normalize()),
+    };
+
+    Some(Hit {
+      t,
+      point: O + D * t,
+      normal: if front_face { N } else { -N },
+      front_face,
+      uv,
+      tangent,
+      bitangent,
+      material: &self.material,
+    })
+  }
+
+  fn bounding_box(&self) -> Option<Aabb> {
+    Some(Aabb::from_points(self.positions.iter().copied()))
+  }
+
+  fn invalidate(&mut self) {
+    self.bvh.take();
+  }
+
+  fn emission(&self) -> Color {
+    self.material.emission
+  }
+
+  /// Picks a face uniformly, then a point on it.
+  #[allow(non_snake_case)]
+  fn sample_towards(
+    &self,
+    P: Position,
+    u: f32,
+    v: f32,
+  ) -> Option<(Position, f32)> {
+    let faces = self.indices.len();
+    if faces == 0 {
+      return None;
+    }
+
+    let scaled = u * faces as f32;
+    let face = (scaled as usize).min(faces - 1);
+    let u = (scaled - face as f32).clamp(0., 1.);
+
+    let (point, solid_angle) = sample_triangle(self.triangle(face), P, u, v)?;
+    Some((point, solid_angle * faces as f32))
+  }
+}
+
+/// Point of the cap of a sphere visible from `P`, drawn uniformly in solid
+/// angle, with the solid angle of the cap. `None` from inside or on the
+/// sphere.
+#[allow(non_snake_case)]
+pub(crate) fn sample_sphere_cap(
+  center: Position,
+  radius: f32,
+  P: Position,
+  u: f32,
+  v: f32,
+) -> Option<(Position, f32)> {
+  let PC = center - P;
+  let distance = PC.magnitude();
+
+  if distance <= radius * 1.001 {
+    return None;
+  }
+
+  let sin2_max = (radius / distance).powi(2);
+  let cos_max = (1. - sin2_max).sqrt();
+  let cos_theta = 1. - u * (1. - cos_max);
+  let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+  let phi = 2. * PI * v;
+
+  let w = PC / distance;
+  let (tangent, bitangent) = orthonormal_basis(w);
+  let D =
+    (tangent * phi.cos() + bitangent * phi.sin()) * sin_theta + w * cos_theta;
+
+  // Nearest intersection, grazing at the edge of the cap
+  let t = distance * cos_theta
+    - (radius * radius - distance * distance * sin_theta * sin_theta)
+      .max(0.)
+      .sqrt();
+
+  Some((P + D * t, 2. * PI * (1. - cos_max)))
+}
+
+/// Uniformly distributed point of a triangle, see [`Shape::sample_towards`].
+#[allow(non_snake_case)]
+fn sample_triangle(
+  [A, B, C]: [Position; 3],
+  P: Position,
+  u: f32,
+  v: f32,
+) -> Option<(Position, f32)> {
+  let su = u.sqrt();
+  let point = A * (1. - su) + B * (su * (1. - v)) + C * (su * v);
+
+  let cross = (B - A).cross(C - A);
+  let area = cross.magnitude() / 2.;
+  if area <= 0. {
+    return None;
+  }
+
+  Some((point, solid_angle(P, point, cross / (2. * area), area)?))
+}
+
+/// Solid angle `area` would cover from `P` if the whole of it were at
+/// `point`, facing `normal` either way.
+#[allow(non_snake_case)]
+fn solid_angle(
+  P: Position,
+  point: Position,
+  normal: Direction,
+  area: f32,
+) -> Option<f32> {
+  let L = point - P;
+  let distance2 = L.magnitude2();
+  let cos = normal.dot(L).abs() / distance2.sqrt();
+
+  (cos > 0.).then(|| area * cos / distance2)
+}
+
+/// Two unit vectors completing `w` into an orthonormal basis.
+pub(crate) fn orthonormal_basis(
+  w: Vector3<f32>,
+) -> (Vector3<f32>, Vector3<f32>) {
+  let a = if w.x.abs() > 0.9 {
+    Vector3::unit_y()
+  } else {
+    Vector3::unit_x()
+  };
+  let u = w.cross(a).normalize();
+  (u, w.cross(u))
+}
+
+/// Unit directions in which `u` and `v` grow over a triangle with edges `e1`
+/// and `e2`, whose texture coordinates change by `d1` and `d2` along them.
+/// `None` when the texture coordinates are degenerate.
+fn uv_derivatives(
+  e1: Vector3<f32>,
+  e2: Vector3<f32>,
+  d1: Vector2<f32>,
+  d2: Vector2<f32>,
+) -> Option<(Vector3<f32>, Vector3<f32>)> {
+  let det = d1.x * d2.y - d2.x * d1.y;
+  if det.abs() < f32::EPSILON {
+    return None;
+  }
+
+  let tangent = (e1 * d2.y - e2 * d1.y) / det;
+  let bitangent = (e2 * d1.x - e1 * d2.x) / det;
+  if tangent.magnitude2() < f32::EPSILON
+    || bitangent.magnitude2() < f32::EPSILON
+  {
+    return None;
+  }
+
+  Some((tangent.normalize(), bitangent.normalize()))
+}
+
+/// * `O` - origin
+/// * `D` - direction
+#[allow(non_snake_case)]
+fn intersect_ray_sphere(
+  O: Vector3<f32>,
+  D: Vector3<f32>,
+  sphere: &Sphere,
+) -> (f32, f32) {
+  let r = sphere.radius;
+  let CO = O - sphere.center;
+
+  let k1 = D.dot(D);
+  let k2 = 2. * CO.dot(D);
+  let k3 = CO.dot(CO) - r * r;
+
+  let discriminant = k2 * k2 - 4. * k1 * k3;
+
+  if discriminant < 0. {
+    return (f32::INFINITY, f32::INFINITY);
+  }
+
+  let t1 = (-k2 + discriminant.sqrt()) / (2. * k1);
+  let t2 = (-k2 - discriminant.sqrt()) / (2. * k1);
+
+  (t1, t2)
+}
+
+/// Returns `t`, the plane normal turned towards the ray origin, so planes
+/// are lit from either side, and whether the ray hit the side `N` faces.
+///
+/// * `O` - origin
+/// * `D` - direction
+/// * `Q` - point on the plane
+/// * `N` - plane normal
+#[allow(non_snake_case)]
+fn intersect_ray_plane(
+  O: Vector3<f32>,
+  D: Vector3<f32>,
+  Q: Vector3<f32>,
+  N: Vector3<f32>,
+) -> Option<(f32, Vector3<f32>, bool)> {
+  let N = N.normalize();
+  let n_dot_d = N.dot(D);
+
+  if n_dot_d.abs() < f32::EPSILON {
+    return None;
+  }
+
+  let t = N.dot(Q - O) / n_dot_d;
+  let front_face = n_dot_d < 0.;
+  let N = if front_face { N } else { -N };
+
+  Some((t, N, front_face))
+}
+
+/// Möller–Trumbore intersection, returning `t` and the barycentric
+/// coordinates `(u, v)` of the hit relative to `B` and `C`.
+///
+/// * `O` - origin
+/// * `D` - direction
+#[allow(non_snake_case)]
+fn intersect_ray_triangle(
+  O: Vector3<f32>,
+  D: Vector3<f32>,
+  A: Vector3<f32>,
+  B: Vector3<f32>,
+  C: Vector3<f32>,
+) -> Option<(f32, f32, f32)> {
+  let AB = B - A;
+  let AC = C - A;
+
+  let P = D.cross(AC);
+  let det = AB.dot(P);
+
+  if det.abs() < f32::EPSILON {
+    return None;
+  }
+
+  let inv_det = 1. / det;
+  let AO = O - A;
+
+  let u = AO.dot(P) * inv_det;
+  if !(0. ..=1.).contains(&u) {
+    return None;
+  }
+
+  let Q = AO.cross(AB);
+  let v = D.dot(Q) * inv_det;
+  if v < 0. || u + v > 1. {
+    return None;
+  }
+
+  let t = AC.dot(Q) * inv_det;
+
+  Some((t, u, v))
+}
@@ -0,0 +1,256 @@
+//! Surface colors varying over a shape, looked up at the UV coordinates of a
+//! hit.
+
+use std::f32::consts::PI;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use cgmath::{InnerSpace, Vector2};
+
+use crate::primitive::{Color, Direction, Position};
+use crate::shape::orthonormal_basis;
+
+/// Replaces the flat `color` of a [`Material`](crate::primitive::Material).
+pub trait Texture: Debug + Send + Sync {
+  /// * `uv` - texture coordinates of the hit, `[0, 1]²` over the shape for
+  ///   bounded ones
+  /// * `point` - hit point, in world space
+  fn color(&self, uv: Vector2<f32>, point: Position) -> Color;
+}
+
+/// UV coordinates of the point of a sphere in `direction` from its center:
+/// `u` goes around the `y` axis, `v` from the bottom pole to the top one.
+pub fn spherical_uv(direction: Direction) -> Vector2<f32> {
+  let d = direction.normalize();
+  let u = 0.5 + d.z.atan2(d.x) / (2. * PI);
+  let v = 0.5 + d.y.clamp(-1., 1.).asin() / PI;
+
+  Vector2::new(u, v)
+}
+
+/// UV coordinates of `offset`, a vector in the plane of `normal`, along two
+/// fixed axes of that plane. One unit of UV is one unit of distance.
+pub fn planar_uv(offset: Direction, normal: Direction) -> Vector2<f32> {
+  let (tangent, bitangent) = orthonormal_basis(normal.normalize());
+  Vector2::new(offset.dot(tangent), offset.dot(bitangent))
+}
+
+/// How texels are blended between their centers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+  Nearest,
+  #[default]
+  Bilinear,
+}
+
+/// What lies outside of `[0, 1]²`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Wrap {
+  #[default]
+  Repeat,
+  /// Repeats, flipping every other copy so the edges match.
+  MirroredRepeat,
+  /// Extends the border texels.
+  ClampToEdge,
+}
+
+impl Wrap {
+  fn texel(self, i: i64, size: u32) -> u32 {
+    let size = size as i64;
+    let i = match self {
+      Self::Repeat => i.rem_euclid(size),
+      Self::MirroredRepeat => {
+        let i = i.rem_euclid(2 * size);
+        if i < size {
+          i
+        } else {
+          2 * size - 1 - i
+        }
+      }
+      Self::ClampToEdge => i.clamp(0, size - 1),
+    };
+
+    i as u32
+  }
+}
+
+/// Decoded pixels, row by row from the top left corner.
+#[derive(Debug, Clone)]
+pub struct Image {
+  width: u32,
+  height: u32,
+  pixels: Vec<Color>,
+}
+
+impl Image {
+  /// `pixels` holds `width × height` linear colors.
+  pub fn new(width: u32, height: u32, pixels: Vec<Color>) -> Self {
+    assert_eq!(pixels.len(), (width * height) as usize);
+    assert!(width > 0 && height > 0, "empty image");
+
+    Self {
+      width,
+      height,
+      pixels,
+    }
+  }
+
+  /// Loads a PNG holding sRGB colors, as most images do.
+  pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+    Self::load_png(path.as_ref(), true)
+  }
+
+  /// Loads a PNG holding data rather than colors, e.g. a normal map, whose
+  /// values are kept as they are.
+  pub fn load_linear(path: impl AsRef<Path>) -> Result<Self> {
+    Self::load_png(path.as_ref(), false)
+  }
+
+  fn load_png(path: &Path, srgb: bool) -> Result<Self> {
+    let file = File::open(path)
+      .with_context(|| format!("failed to open {}", path.display()))?;
+
+    let mut decoder = png::Decoder::new(BufReader::new(file));
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder
+      .read_info()
+      .with_context(|| format!("failed to decode {}", path.display()))?;
+
+    let mut data = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut data)?;
+    let data = &data[..frame.buffer_size()];
+
+    let channels = match frame.color_type {
+      png::ColorType::Grayscale => 1,
+      png::ColorType::GrayscaleAlpha => 2,
+      png::ColorType::Rgb => 3,
+      png::ColorType::Rgba => 4,
+      png::ColorType::Indexed => {
+        bail!("{}: unexpanded palette", path.display())
+      }
+    };
+
+    let values: Vec<f32> = match frame.bit_depth {
+      png::BitDepth::Sixteen => data
+        .chunks_exact(2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]) as f32 / 65535.)
+        .collect(),
+      _ => data.iter().map(|&byte| byte as f32 / 255.).collect(),
+    };
+
+    let pixels = values
+      .chunks_exact(channels)
+      .map(|texel| {
+        let [r, g, b] = match texel {
+          [gray] | [gray, _] => [*gray; 3],
+          [r, g, b, ..] => [*r, *g, *b],
+          _ => unreachable!(),
+        };
+
+        if srgb {
+          Color::new(r, g, b).to_linear()
+        } else {
+          Color::new(r, g, b)
+        }
+      })
+      .collect();
+
+    Ok(Self::new(frame.width, frame.height, pixels))
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  pub fn pixel(&self, x: u32, y: u32) -> Color {
+    self.pixels[(x + y * self.width) as usize]
+  }
+}
+
+/// An [`Image`] stretched once over `[0, 1]²`, or `scale` times.
+#[derive(Debug, Clone)]
+pub struct ImageTexture {
+  image: Arc<Image>,
+  interpolation: Interpolation,
+  wrap: Wrap,
+  scale: Vector2<f32>,
+}
+
+impl ImageTexture {
+  pub fn new(image: impl Into<Arc<Image>>) -> Self {
+    Self {
+      image: image.into(),
+      interpolation: Interpolation::default(),
+      wrap: Wrap::default(),
+      scale: Vector2::new(1., 1.),
+    }
+  }
+
+  /// Loads an sRGB PNG, see [`Image::load`].
+  pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+    Ok(Self::new(Image::load(path)?))
+  }
+
+  pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+    self.interpolation = interpolation;
+    self
+  }
+
+  pub fn with_wrap(mut self, wrap: Wrap) -> Self {
+    self.wrap = wrap;
+    self
+  }
+
+  pub fn with_scale(mut self, u: f32, v: f32) -> Self {
+    self.scale = Vector2::new(u, v);
+    self
+  }
+
+  pub fn image(&self) -> &Image {
+    &self.image
+  }
+
+  fn texel(&self, x: i64, y: i64) -> Color {
+    let image = &self.image;
+    let x = self.wrap.texel(x, image.width);
+    let y = self.wrap.texel(y, image.height);
+    image.pixel(x, y)
+  }
+}
+
+impl Texture for ImageTexture {
+  fn color(&self, uv: Vector2<f32>, _point: Position) -> Color {
+    // In texels, with their centers on integers and v pointing up
+    let x = uv.x * self.scale.x * self.image.width as f32 - 0.5;
+    let y = (1. - uv.y * self.scale.y) * self.image.height as f32 - 0.5;
+
+    match self.interpolation {
+      Interpolation::Nearest => self.texel(x.round() as i64, y.round() as i64),
+      Interpolation::Bilinear => {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(x0, y0).lerp(self.texel(x0 + 1, y0), fx);
+        let bottom =
+          self.texel(x0, y0 + 1).lerp(self.texel(x0 + 1, y0 + 1), fx);
+        top.lerp(bottom, fy)
+      }
+    }
+  }
+}
+
+/// The same color everywhere.
+impl Texture for Color {
+  fn color(&self, _uv: Vector2<f32>, _point: Position) -> Color {
+    *self
+  }
+}
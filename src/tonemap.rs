@@ -0,0 +1,38 @@
+//! Operators compressing HDR colors into the `0..=1` range of the 8-bit
+//! buffer.
+
+use crate::primitive::Color;
+
+/// Applied per channel, after the exposure has scaled the color.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ToneMap {
+  /// Cuts everything above 1, which is what plain 8-bit output does.
+  #[default]
+  Clamp,
+  /// `c / (1 + c)`: never saturates, but also dims mid tones.
+  Reinhard,
+  /// Reinhard with `white` and above mapped to 1.
+  ExtendedReinhard { white: f32 },
+  /// Narkowicz's fit of the ACES filmic curve.
+  AcesFilmic,
+}
+
+impl ToneMap {
+  pub fn apply(self, color: Color) -> Color {
+    let map = |c: f32| {
+      let c = c.max(0.);
+      match self {
+        Self::Clamp => c,
+        Self::Reinhard => c / (1. + c),
+        Self::ExtendedReinhard { white } => {
+          c * (1. + c / (white * white)) / (1. + c)
+        }
+        Self::AcesFilmic => {
+          (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14)
+        }
+      }
+    };
+
+    Color::new(map(color.r), map(color.g), map(color.b)).clamp()
+  }
+}